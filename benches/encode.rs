@@ -0,0 +1,50 @@
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use my_sonic_client::frame::codec::ClientCodec;
+use my_sonic_client::frame::send::{Push, Send};
+use tokio_util::codec::Encoder;
+
+const PUSHES: u64 = 1_000;
+
+/// A bulk ingest, written through the same buffer as `Framed` does.
+fn encode(c: &mut Criterion) {
+    let pushes: Vec<Push> = (0..PUSHES)
+        .map(|i| {
+            Push::new(
+                "messages".into(),
+                format!("user:{:08x}", i % 10),
+                format!("conversation:{:08x}", i),
+                "Hello Valerian Saliou, how are you \"today\"?".into(),
+            )
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(PUSHES));
+    group.bench_function("bulk_ingest", |b| {
+        let mut codec = ClientCodec::default();
+        let mut dst = BytesMut::with_capacity(8 * 1024);
+        // Frames are built outside of the timed loop, only encoding is measured.
+        b.iter_batched(
+            || {
+                pushes
+                    .iter()
+                    .cloned()
+                    .map(Send::Push)
+                    .collect::<Vec<Send>>()
+            },
+            |frames| {
+                for frame in frames {
+                    codec.encode(frame, &mut dst).expect("Failed to encode");
+                    // As if flushed to the socket.
+                    dst.clear();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
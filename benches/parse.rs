@@ -0,0 +1,42 @@
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use my_sonic_client::frame::codec::ClientCodec;
+use tokio_util::codec::Decoder;
+
+/// A search session as read from the server: greetings, then many queries.
+fn search_session() -> Vec<u8> {
+    let mut src = Vec::new();
+    src.extend_from_slice(b"CONNECTED <sonic-server v1.10.2>\r\n");
+    src.extend_from_slice(b"STARTED search protocol(1) buffer(20000)\r\n");
+    for i in 0..1_000 {
+        src.extend_from_slice(format!("PENDING Bt2m{:04}\r\n", i).as_bytes());
+        src.extend_from_slice(format!("EVENT QUERY Bt2m{:04}", i).as_bytes());
+        for key in 0..10 {
+            src.extend_from_slice(format!(" conversation:{:08x}", i * 10 + key).as_bytes());
+        }
+        src.extend_from_slice(b"\r\n");
+    }
+    src
+}
+
+fn decode(c: &mut Criterion) {
+    let session = search_session();
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(session.len() as u64));
+    group.bench_function("search_session", |b| {
+        b.iter(|| {
+            let mut codec = ClientCodec::default();
+            let mut src = BytesMut::from(&session[..]);
+            let mut frames = 0;
+            while let Some(_frame) = codec.decode(&mut src).expect("Failed to decode") {
+                frames += 1;
+            }
+            frames
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+use my_sonic_client::connection::Connection;
+use my_sonic_client::frame::send::FlushObject;
+use my_sonic_client::session::Ingest;
+use std::env;
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().expect("Failed to read .env file.");
+
+    let host = env::var("HOST").expect("Environment var `HOST` not found");
+    let port = env::var("PORT").expect("Environment var `PORT` not found");
+    let passwd = env::var("PASSWORD").expect("Environment var `PASSWORD` not found");
+
+    let mut session = Connection::connect(format!("[{}]:{}", host, port), Ingest, passwd)
+        .await
+        .expect("Failed to start `ingest` session");
+
+    let info = session.session_info();
+    println!("Sonic version: {}", session.server_info().version);
+    println!(
+        "Mode: {:?}, protocol: {}, buffer_size: {}",
+        session.mode(),
+        info.protocol,
+        info.buffer_size
+    );
+
+    let count = session
+        .flush_object(FlushObject::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "conversation:71f3d63c".into(),
+        ))
+        .await
+        .expect("Failed to send `FLUSHO messages`");
+    println!("Flushed: {}", count);
+
+    let reason = session.quit().await.expect("Failed to send `QUIT`");
+    println!("End connection: {}", reason);
+}
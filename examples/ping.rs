@@ -1,9 +1,6 @@
 use my_sonic_client::connection::Connection;
-use my_sonic_client::frame::recv::Recv;
-use my_sonic_client::frame::send::Send;
-use my_sonic_client::frame::Mode;
+use my_sonic_client::session::Ingest;
 use std::env;
-use tokio::net::TcpStream;
 
 #[tokio::main]
 async fn main() {
@@ -13,42 +10,24 @@ async fn main() {
     let port = env::var("PORT").expect("Environment var `PORT` not found");
     let passwd = env::var("PASSWORD").expect("Environment var `PASSWORD` not found");
 
-    let socket = TcpStream::connect(format!("[{}]:{}", host, port))
+    let mut session = Connection::connect(format!("[{}]:{}", host, port), Ingest, passwd)
         .await
-        .expect("Failed to create TcpStream connection.");
+        .expect("Failed to start `ingest` session");
 
-    let mut connection = Connection::new(socket);
-
-    if let Ok(Recv::Connected(version)) = connection.read_frame().await {
-        println!("Protocol version: {}", version);
-    }
-
-    connection
-        .write_frame(Send::Start(Mode::Ingest, passwd))
-        .await
-        .expect("Failed to send `START ingest`");
-
-    if let Ok(Recv::Started(Some(mode), size)) = connection.read_frame().await {
-        println!("Mode: {:?}, buffer_size: {}", mode, size);
-    }
+    let info = session.session_info();
+    println!("Sonic version: {}", session.server_info().version);
+    println!(
+        "Mode: {:?}, protocol: {}, buffer_size: {}",
+        session.mode(),
+        info.protocol,
+        info.buffer_size
+    );
 
     println!("Ping");
 
-    connection
-        .write_frame(Send::Ping)
-        .await
-        .expect("Failed to send `PING messages`");
-
-    if let Ok(Recv::Pong) = connection.read_frame().await {
-        println!("Pong");
-    }
-
-    connection
-        .write_frame(Send::Quit)
-        .await
-        .expect("Failed to send `QUIT messages`");
+    session.ping().await.expect("Failed to send `PING`");
+    println!("Pong");
 
-    if let Ok(Recv::Ended(_host)) = connection.read_frame().await {
-        println!("End connection");
-    }
+    let reason = session.quit().await.expect("Failed to send `QUIT`");
+    println!("End connection: {}", reason);
 }
@@ -1,10 +1,7 @@
 use my_sonic_client::connection::Connection;
-use my_sonic_client::frame::recv::Recv;
 use my_sonic_client::frame::send::Push;
-use my_sonic_client::frame::send::Send;
-use my_sonic_client::frame::Mode;
+use my_sonic_client::session::Ingest;
 use std::env;
-use tokio::net::TcpStream;
 
 #[tokio::main]
 async fn main() {
@@ -14,45 +11,30 @@ async fn main() {
     let port = env::var("PORT").expect("Environment var `PORT` not found");
     let passwd = env::var("PASSWORD").expect("Environment var `PASSWORD` not found");
 
-    let socket = TcpStream::connect(format!("[{}]:{}", host, port))
+    let mut session = Connection::connect(format!("[{}]:{}", host, port), Ingest, passwd)
         .await
-        .expect("Failed to create TcpStream connection.");
-
-    let mut connection = Connection::new(socket);
-
-    if let Ok(Recv::Connected(version)) = connection.read_frame().await {
-        println!("Protocol version: {}", version);
-    }
-
-    connection
-        .write_frame(Send::Start(Mode::Ingest, passwd))
-        .await
-        .expect("Failed to send `START ingest`");
-
-    if let Ok(Recv::Started(Some(mode), size)) = connection.read_frame().await {
-        println!("Mode: {:?}, buffer_size: {}", mode, size);
-    }
-
-    connection
-        .write_frame(Send::Push(Push::new(
+        .expect("Failed to start `ingest` session");
+
+    let info = session.session_info();
+    println!("Sonic version: {}", session.server_info().version);
+    println!(
+        "Mode: {:?}, protocol: {}, buffer_size: {}",
+        session.mode(),
+        info.protocol,
+        info.buffer_size
+    );
+
+    session
+        .push(Push::new(
             "messages".into(),
             "user:0dcde3a6".into(),
             "conversation:71f3d63c".into(),
             "Hello, how are you today?".into(),
-        )))
+        ))
         .await
         .expect("Failed to send `PUSH messages`");
+    println!("Push Ok");
 
-    if let Ok(Recv::Ok) = connection.read_frame().await {
-        println!("Push Ok");
-    }
-
-    connection
-        .write_frame(Send::Quit)
-        .await
-        .expect("Failed to send `QUIT messages`");
-
-    if let Ok(Recv::Ended(_host)) = connection.read_frame().await {
-        println!("End connection");
-    }
+    let reason = session.quit().await.expect("Failed to send `QUIT`");
+    println!("End connection: {}", reason);
 }
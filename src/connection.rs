@@ -1,90 +1,712 @@
-use crate::frame::recv::Recv;
-use crate::frame::send::Send;
+use crate::frame::codec::ClientCodec;
+use crate::frame::recv::{Capabilities, Recv, ServerInfo, SessionInfo};
+use crate::frame::send::{Push, Send};
+use crate::frame::Parsing;
+use crate::session::{Session, SessionMode};
 use crate::Error;
-use bytes::{Buf, BytesMut};
-use std::io::{self, Cursor};
-use tokio::io::BufWriter;
-use tokio::net::TcpStream;
+use futures::{SinkExt, StreamExt};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, ToSocketAddrs};
 use tokio::prelude::*;
+use tokio_util::codec::Framed;
 
-/// Send and receive frame.
+/// Send and receive frame, over any stream: TCP by default, but also
+/// e.g. a Unix domain socket, or an in-memory pipe in tests.
 #[derive(Debug)]
-pub struct Connection {
-    stream: BufWriter<TcpStream>,
-    // When Tokio v0.3 change to tokio::BytesMut
-    buffer: BytesMut,
+pub struct Connection<T = TcpStream> {
+    // Dropped, closing the stream, once a deadline passed.
+    framed: Option<Framed<T, ClientCodec>>,
+    timeouts: Timeouts,
+    // A command was written, and its reply isn't fully read yet.
+    outstanding: bool,
+    // Read on `CONNECTED` and `STARTED`.
+    server: Option<ServerInfo>,
+    session: Option<SessionInfo>,
+    // Answer to `HELP commands`, asked on the first `write_supported`.
+    capabilities: Option<Capabilities>,
+    // Told apart in traces.
+    id: u64,
+    trace: bool,
 }
 
-impl Connection {
-    /// Create new `Connection`, backed by `socket`.
-    pub fn new(socket: TcpStream) -> Connection {
+// Ids of the `Connection`s, in order of creation.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The command isn't available on the server, for the current mode.
+#[derive(Debug)]
+pub struct Unsupported(pub String);
+
+impl std::error::Error for Unsupported {}
+impl fmt::Display for Unsupported {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "unsupported by server; `{}`", self.0)
+    }
+}
+
+/// How long a `Connection` waits, without limit by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Timeouts {
+    connect: Option<Duration>,
+    handshake: Option<Duration>,
+    command: Option<Duration>,
+    idle: Option<Duration>,
+}
+
+impl Timeouts {
+    /// To open the TCP connection.
+    pub fn connect(mut self, timeout: Duration) -> Self {
+        self.connect = Some(timeout);
+        self
+    }
+
+    /// To read `CONNECTED`, then `START` a session.
+    pub fn handshake(mut self, timeout: Duration) -> Self {
+        self.handshake = Some(timeout);
+        self
+    }
+
+    /// To write a frame, then to read each frame of its reply,
+    /// e.g. `PENDING` then `EVENT`.
+    pub fn command(mut self, timeout: Duration) -> Self {
+        self.command = Some(timeout);
+        self
+    }
+
+    /// To read a frame when no reply is awaited.
+    pub fn idle(mut self, timeout: Duration) -> Self {
+        self.idle = Some(timeout);
+        self
+    }
+}
+
+/// A timeout passed, the connection is closed: it can't tell anymore
+/// which reply belongs to which command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimedOut {
+    Connect,
+    Handshake,
+    Command,
+    Idle,
+}
+
+impl std::error::Error for TimedOut {}
+impl fmt::Display for TimedOut {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimedOut::Connect => fmt.write_str("timed out; connect"),
+            TimedOut::Handshake => fmt.write_str("timed out; handshake"),
+            TimedOut::Command => fmt.write_str("timed out; command"),
+            TimedOut::Idle => fmt.write_str("timed out; idle"),
+        }
+    }
+}
+
+/// Await `future`, failing with `timed_out` after `timeout`, if any.
+async fn within<F: Future>(
+    timeout: Option<Duration>,
+    timed_out: TimedOut,
+    future: F,
+) -> Result<F::Output, TimedOut> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .map_err(|_elapsed| timed_out),
+        None => Ok(future.await),
+    }
+}
+
+impl Connection<TcpStream> {
+    /// Open a TCP connection to `addr`. Nothing is read yet, not even `CONNECTED`.
+    pub async fn open<A: ToSocketAddrs>(addr: A) -> io::Result<Connection> {
+        Ok(Connection::new(TcpStream::connect(addr).await?))
+    }
+
+    /// Open a TCP connection to `addr`, then `handshake`.
+    pub async fn connect<A: ToSocketAddrs, M: SessionMode>(
+        addr: A,
+        mode: M,
+        password: String,
+    ) -> Result<Session<M>, Error> {
+        Connection::connect_with(addr, mode, password, Timeouts::default()).await
+    }
+
+    /// `connect`, within `timeouts`, kept for the session.
+    ///
+    /// Fails with `TimedOut` if one passes.
+    pub async fn connect_with<A: ToSocketAddrs, M: SessionMode>(
+        addr: A,
+        mode: M,
+        password: String,
+        timeouts: Timeouts,
+    ) -> Result<Session<M>, Error> {
+        let stream = within(
+            timeouts.connect,
+            TimedOut::Connect,
+            TcpStream::connect(addr),
+        )
+        .await??;
+
+        let mut connection = Connection::new(stream);
+        connection.set_timeouts(timeouts);
+
+        connection.handshake(mode, password).await
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Connection<T> {
+    /// Create new `Connection`, backed by `stream`.
+    pub fn new(stream: T) -> Connection<T> {
         Connection {
-            stream: BufWriter::new(socket),
             // For now 4KB is the default, this may change based on the use cases.
-            buffer: BytesMut::with_capacity(4 * 1024),
+            framed: Some(Framed::with_capacity(
+                stream,
+                ClientCodec::default(),
+                4 * 1024,
+            )),
+            timeouts: Timeouts::default(),
+            outstanding: false,
+            server: None,
+            session: None,
+            capabilities: None,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            trace: false,
         }
     }
 
-    /// Write a `Send` Frame into the `self.stream`.
-    pub async fn write_frame(&mut self, frame: Send) -> io::Result<()> {
-        self.write_string(frame.to_string()).await
+    /// Read `CONNECTED`, unless already read, then `START` a session on `mode`,
+    /// e.g. `session::Search`.
+    ///
+    /// Fails with `session::HandshakeError` if the password is rejected,
+    /// or the session doesn't start on `mode`, and with `TimedOut` past
+    /// the handshake timeout.
+    pub async fn handshake<M: SessionMode>(
+        self,
+        _mode: M,
+        password: String,
+    ) -> Result<Session<M, T>, Error> {
+        let timeout = self.timeouts.handshake;
+
+        within(timeout, TimedOut::Handshake, Session::start(self, password)).await?
     }
 
-    /// Write a `String` into the `self.stream`.
-    pub async fn write_string(&mut self, frame: String) -> io::Result<()> {
-        self.stream.write_all(&frame.into_bytes()).await?;
+    /// Identifies the connection in traces.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
 
-        self.stream.flush().await
+    /// Trace every frame sent and received, off by default.
+    ///
+    /// Frames are `tracing` events, at the `DEBUG` level, with the connection
+    /// `id` and the time it took to write or to wait for them.
+    /// The `START` password is never traced.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
     }
 
-    /// Read `self.buffer` into a `Recv` Frame.
-    pub async fn read_frame(&mut self) -> Result<Recv, Error> {
-        loop {
-            let mut buf = Cursor::new(&self.buffer[..]);
+    /// How long to wait, without limit by default.
+    ///
+    /// Once a timeout passed, the stream is closed: reads and writes fail
+    /// with `io::ErrorKind::NotConnected`.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
 
-            match Recv::check(&mut buf) {
-                Ok(_) => {
-                    // TODO Some kind of debug mode.
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
 
-                    let len = buf.position() as usize;
+    /// Whether the stream was closed: after a timeout, a line that couldn't
+    /// be read, or by the remote.
+    pub fn is_closed(&self) -> bool {
+        self.framed.is_none()
+    }
 
-                    // Set position to Zero before parsing.
-                    buf.set_position(0);
-                    let frame = Recv::parse(&mut buf)?;
-                    self.buffer.advance(len);
+    /// The server greeting, once `CONNECTED` is read.
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.server.as_ref()
+    }
 
-                    return Ok(frame);
-                }
-                Err(crate::frame::Error::Incomplete) => {}
-                Err(e) => return Err(e.into()),
+    /// The negotiated session, once `STARTED` is read.
+    pub fn session_info(&self) -> Option<&SessionInfo> {
+        self.session.as_ref()
+    }
+
+    /// How to read lines that aren't part of the protocol, `Parsing::Strict` by default.
+    pub fn set_parsing(&mut self, parsing: Parsing) {
+        if let Some(framed) = &mut self.framed {
+            framed.codec_mut().set_parsing(parsing);
+        }
+    }
+
+    /// Longest line read, in bytes, `codec::DEFAULT_MAX_LENGTH` by default.
+    ///
+    /// `read_frame` fails with `codec::LineTooLong` on longer lines, and closes
+    /// the stream, as on any line it can't read.
+    pub fn set_max_line_length(&mut self, max_length: usize) {
+        if let Some(framed) = &mut self.framed {
+            framed.codec_mut().set_max_length(max_length);
+        }
+    }
+
+    /// Write a `Send` Frame into the stream.
+    ///
+    /// Fails, writing nothing, if an argument would break the frame,
+    /// and with `TimedOut` past the command timeout.
+    pub async fn write_frame(&mut self, frame: Send) -> Result<(), Error> {
+        let line = if self.trace {
+            Some(Traced(&frame).to_string())
+        } else {
+            None
+        };
+
+        let timeout = self.timeouts.command;
+        let start = Instant::now();
+        let res = within(timeout, TimedOut::Command, self.framed()?.send(frame)).await;
+
+        if let Some(line) = line {
+            tracing::debug!(
+                connection = self.id,
+                elapsed = ?start.elapsed(),
+                ok = matches!(res, Ok(Ok(()))),
+                "sent {}",
+                line
+            );
+        }
+
+        let res = self.close_on(res)?;
+        if res.is_ok() {
+            self.outstanding = true;
+        }
+
+        res
+    }
+
+    /// Commands available on the current mode, asked once with `HELP commands`.
+    pub async fn capabilities(&mut self) -> Result<&Capabilities, Error> {
+        if self.capabilities.is_none() {
+            self.write_frame(Send::Help(Some("commands".to_string())))
+                .await?;
+
+            match self.read_frame().await? {
+                Recv::Commands(capabilities) => self.capabilities = Some(capabilities),
+                frame => return Err(format!("unexpected frame; {:?}", frame).into()),
             }
+        }
 
-            if 0 == self
-                .stream
-                .read_buf(&mut self.buffer)
-                .await
-                .expect("Failed trying to read_buf")
-            {
-                // Mini-redis:
-                // The remote closed the connection. For this to be a clean
-                // shutdown, there should be no data in the read buffer. If
-                // there is, this means that the peer closed the socket while
-                // sending a frame.
-                if self.buffer.is_empty() {
-                    return Ok(Recv::Ended("Remote".to_string()));
-                } else {
-                    return Err("connection reset by peer".into());
+        Ok(self.capabilities.get_or_insert_with(Capabilities::default))
+    }
+
+    /// Whether the server accepts `frame`, on the current mode.
+    ///
+    /// For `TRIGGER`, the action is checked too, against the actions
+    /// listed by `TRIGGER` alone, asked once.
+    pub async fn supports(&mut self, frame: &Send) -> Result<bool, Error> {
+        let command = frame.command();
+        let action = match frame {
+            Send::Start(..) => return Ok(true),
+            Send::Trigger(trigger) => trigger.action(),
+            _ => return Ok(self.capabilities().await?.contains(command)),
+        };
+
+        let capabilities = self.capabilities().await?;
+        if capabilities.contains(command) && !capabilities.actions.contains_key(command) {
+            self.write_string(format!("{}\r\n", command)).await?;
+
+            match self.read_frame().await? {
+                Recv::ResultPairs(pairs) => match pairs.as_slice() {
+                    [(key, actions)] if key == "actions" => {
+                        if let Some(capabilities) = &mut self.capabilities {
+                            capabilities.set_actions(command, actions);
+                        }
+                    }
+                    _ => return Err(format!("unexpected frame; {:?}", pairs).into()),
+                },
+                frame => return Err(format!("unexpected frame; {:?}", frame).into()),
+            }
+        }
+
+        Ok(self.capabilities().await?.contains_action(command, action))
+    }
+
+    /// Write `frame`, or fail with `Unsupported` if the server doesn't accept it,
+    /// instead of waiting for its `ERR`.
+    pub async fn write_supported(&mut self, frame: Send) -> Result<(), Error> {
+        if !self.supports(&frame).await? {
+            let command = match &frame {
+                Send::Trigger(trigger) => format!("{} {}", frame.command(), trigger.action()),
+                frame => frame.command().to_string(),
+            };
+
+            return Err(Unsupported(command).into());
+        }
+
+        self.write_frame(frame).await
+    }
+
+    /// Write a `Push`, split in as many `PUSH` as needed to fit the buffer size
+    /// negotiated on `STARTED`, reading the reply to each of them.
+    ///
+    /// Returns `Recv::Ok` if every part was accepted, otherwise the first
+    /// other reply, in which case the remaining parts are not sent.
+    pub async fn push(&mut self, push: Push) -> Result<Recv, Error> {
+        let parts = match &self.session {
+            Some(session) => push.split(session.buffer_size as usize),
+            None => vec![push],
+        };
+
+        for part in parts {
+            self.write_frame(Send::Push(part)).await?;
+
+            match self.read_frame().await? {
+                Recv::Ok => {}
+                frame => return Ok(frame),
+            }
+        }
+
+        Ok(Recv::Ok)
+    }
+
+    /// Write a `String` into the stream, as is.
+    ///
+    /// Fails with `io::ErrorKind::TimedOut`, holding `TimedOut`, past the
+    /// command timeout.
+    pub async fn write_string(&mut self, frame: String) -> io::Result<()> {
+        if self.trace {
+            // Not a frame, it may hold a password.
+            tracing::debug!(connection = self.id, "sent {} raw bytes", frame.len());
+        }
+
+        let timeout = self.timeouts.command;
+        let stream = self.framed()?.get_mut();
+        let res = within(timeout, TimedOut::Command, async {
+            stream.write_all(&frame.into_bytes()).await?;
+
+            stream.flush().await
+        })
+        .await;
+
+        let res = self
+            .close_on(res)
+            .map_err(|timed_out| io::Error::new(io::ErrorKind::TimedOut, timed_out))?;
+        if res.is_ok() {
+            self.outstanding = true;
+        }
+
+        res
+    }
+
+    /// Read a `Recv` Frame from the stream.
+    ///
+    /// An `ERR` reply is returned as an `Err`, holding the `ServerError`,
+    /// and a line that can't be read fails, closing the stream.
+    /// Fails with `TimedOut` past the command timeout, while a reply is
+    /// awaited, otherwise past the idle timeout.
+    pub async fn read_frame(&mut self) -> Result<Recv, Error> {
+        let (timeout, timed_out) = if self.outstanding {
+            (self.timeouts.command, TimedOut::Command)
+        } else {
+            (self.timeouts.idle, TimedOut::Idle)
+        };
+
+        let start = Instant::now();
+        let frame = within(timeout, timed_out, self.framed()?.next()).await;
+
+        if self.trace {
+            let elapsed = start.elapsed();
+            match &frame {
+                Ok(Some(Ok(frame))) => tracing::debug!(
+                    connection = self.id,
+                    ?elapsed,
+                    "received {}",
+                    frame.to_string().trim_end()
+                ),
+                Ok(Some(Err(err))) => {
+                    tracing::debug!(connection = self.id, ?elapsed, "failed; {}", err)
+                }
+                Ok(None) => tracing::debug!(connection = self.id, ?elapsed, "closed by remote"),
+                Err(timed_out) => {
+                    tracing::debug!(connection = self.id, ?elapsed, "failed; {}", timed_out)
                 }
             }
         }
+
+        let frame = match self.close_on(frame)? {
+            Some(Ok(frame)) => frame,
+            // The rest of the reply, if any, couldn't be told apart
+            // from the reply to the next command.
+            Some(Err(err)) => {
+                self.close();
+                return Err(err);
+            }
+            // The remote closed the connection, `Framed` already failed
+            // if it was in the middle of a frame.
+            None => {
+                self.close();
+                return Ok(Recv::Ended("Remote".to_string()));
+            }
+        };
+
+        // `EVENT` follows.
+        self.outstanding = matches!(frame, Recv::Pending(_));
+
+        match &frame {
+            Recv::Connected(server) => self.server = Some(server.clone()),
+            Recv::Started(session) => {
+                self.session = Some(session.clone());
+                // Commands depend on the mode.
+                self.capabilities = None;
+            }
+            _ => {}
+        }
+
+        if let Recv::Err(err) = frame {
+            return Err(err.into());
+        }
+
+        Ok(frame)
+    }
+
+    fn framed(&mut self) -> io::Result<&mut Framed<T, ClientCodec>> {
+        self.framed
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "connection closed"))
+    }
+
+    /// Close the stream if `res` timed out.
+    fn close_on<R>(&mut self, res: Result<R, TimedOut>) -> Result<R, TimedOut> {
+        if res.is_err() {
+            self.close();
+        }
+
+        res
+    }
+
+    fn close(&mut self) {
+        self.framed = None;
+        self.outstanding = false;
+    }
+}
+
+/// A frame as traced: without its `\r\n`, nor the `START` password.
+struct Traced<'a>(&'a Send);
+
+impl fmt::Display for Traced<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Send::Start(mode, _passwd) => write!(fmt, "START {} ********", mode),
+            frame => frame.to_string().trim_end().fmt(fmt),
+        }
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
-    use crate::frame::send::{Push, Query};
+    use crate::frame::codec::{LineTooLong, ServerCodec};
+    use crate::frame::recv::Version;
+    use crate::frame::send::{Push, Query, Trigger};
     use crate::frame::Mode;
+    use crate::session::Search;
+    use tokio::net::UnixStream;
+    use tokio_util::codec::LinesCodec;
+
+    #[test]
+    fn connection_traced() {
+        assert_eq!(
+            "START search ********",
+            Traced(&Send::Start(Mode::Search, "SecretPassword".into())).to_string()
+        );
+        assert_eq!(
+            "QUERY messages user:0dcde3a6 \"valerian saliou\"",
+            Traced(&Send::Query(Query::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "valerian saliou".into()
+            )))
+            .to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_unix_stream() {
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+
+        let server = tokio::spawn(async move {
+            let mut server = Framed::new(server, ServerCodec::default());
+
+            server
+                .send(Recv::Connected(ServerInfo {
+                    version: Version::new(1, 2, 3),
+                }))
+                .await
+                .expect("Failed to send `CONNECTED`");
+
+            match server.next().await {
+                Some(Ok(Send::Ping)) => server.send(Recv::Pong).await,
+                frame => panic!("unexpected frame; {:?}", frame),
+            }
+        });
+
+        let mut connection = Connection::new(client);
+
+        match connection.read_frame().await {
+            Ok(Recv::Connected(_server)) => {}
+            frame => panic!("unexpected frame; {:?}", frame),
+        }
+        assert_eq!(
+            Some(Version::new(1, 2, 3)),
+            connection.server_info().map(|server| server.version)
+        );
+
+        connection
+            .write_frame(Send::Ping)
+            .await
+            .expect("Failed to send `PING`");
+
+        assert_eq!(
+            Recv::Pong,
+            connection
+                .read_frame()
+                .await
+                .expect("Failed to read `PONG`")
+        );
+
+        server
+            .await
+            .expect("Failed to run server")
+            .expect("Failed to send `PONG`");
+
+        // The server is gone.
+        assert_eq!(
+            Recv::Ended("Remote".into()),
+            connection.read_frame().await.expect("Failed to read end")
+        );
+        assert!(connection.is_closed());
+    }
+
+    #[tokio::test]
+    async fn connection_decode_error() {
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+
+        // Answering `QUERY` with an `EVENT` too long to read.
+        let server = tokio::spawn(async move {
+            let mut server = Framed::new(server, ServerCodec::default());
+
+            match server.next().await {
+                Some(Ok(Send::Query(_query))) => {}
+                frame => panic!("unexpected frame; {:?}", frame),
+            }
+            for frame in [
+                Recv::Pending("Bt2m2gYa".into()),
+                Recv::EventQuery(
+                    "Bt2m2gYa".into(),
+                    vec!["conversation:71f3d63b", "conversation:6501e83a"].into(),
+                ),
+            ] {
+                server.send(frame).await.expect("Failed to send reply");
+            }
+            server.next().await.is_none()
+        });
+
+        let mut connection = Connection::new(client);
+        connection.set_max_line_length(32);
+
+        connection
+            .write_frame(Send::Query(Query::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "valerian".into(),
+            )))
+            .await
+            .expect("Failed to send `QUERY`");
+        assert_eq!(
+            Recv::Pending("Bt2m2gYa".into()),
+            connection
+                .read_frame()
+                .await
+                .expect("Failed to read `PENDING`")
+        );
+
+        match connection.read_frame().await {
+            Err(err) => assert!(err.is::<LineTooLong>()),
+            frame => panic!("unexpected frame; {:?}", frame),
+        }
+
+        // Closed, on both ends.
+        assert!(connection.is_closed());
+        assert!(connection.write_frame(Send::Ping).await.is_err());
+        assert!(server.await.expect("Failed to run server"));
+    }
+
+    fn timed_out<R: fmt::Debug>(res: Result<R, Error>) -> TimedOut {
+        match res {
+            Err(err) => *err.downcast_ref::<TimedOut>().expect("Failed to time out"),
+            res => panic!("unexpected result; {:?}", res),
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_timeouts() {
+        let timeouts = Timeouts::default()
+            .command(Duration::from_millis(50))
+            .idle(Duration::from_millis(50));
+
+        // A server reading `PING`, then stalling.
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let server = tokio::spawn(async move {
+            let mut server = Framed::new(server, ServerCodec::default());
+
+            match server.next().await {
+                Some(Ok(Send::Ping)) => {}
+                frame => panic!("unexpected frame; {:?}", frame),
+            }
+            server.next().await.is_none()
+        });
+
+        let mut connection = Connection::new(client);
+        connection.set_timeouts(timeouts);
+
+        connection
+            .write_frame(Send::Ping)
+            .await
+            .expect("Failed to send `PING`");
+        assert_eq!(TimedOut::Command, timed_out(connection.read_frame().await));
+
+        // Closed, on both ends.
+        assert!(connection.is_closed());
+        match connection.write_frame(Send::Ping).await {
+            Err(err) => assert_eq!(
+                Some(io::ErrorKind::NotConnected),
+                err.downcast_ref::<io::Error>().map(io::Error::kind)
+            ),
+            res => panic!("unexpected result; {:?}", res),
+        }
+        assert!(server.await.expect("Failed to run server"));
+
+        // Nothing asked.
+        let (client, _server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let mut connection = Connection::new(client);
+        connection.set_timeouts(timeouts);
+
+        assert_eq!(TimedOut::Idle, timed_out(connection.read_frame().await));
+        assert!(connection.is_closed());
+
+        // No `CONNECTED`.
+        let (client, _server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let mut connection = Connection::new(client);
+        connection.set_timeouts(Timeouts::default().handshake(Duration::from_millis(50)));
+
+        assert_eq!(
+            TimedOut::Handshake,
+            timed_out(connection.handshake(Search, "SecretPassword".into()).await)
+        );
+    }
 
     #[tokio::test]
     async fn ingest_mode() {
@@ -94,8 +716,8 @@ mod test {
 
         let mut connection = Connection::new(socket);
 
-        if let Ok(Recv::Connected(version)) = connection.read_frame().await {
-            assert_eq!("1.2.3".to_string(), version);
+        if let Ok(Recv::Connected(server)) = connection.read_frame().await {
+            assert_eq!(Version::new(1, 2, 3), server.version);
         }
 
         connection
@@ -103,8 +725,8 @@ mod test {
             .await
             .expect("Failed to send `START ingest`");
 
-        if let Ok(Recv::Started(mode, _size)) = connection.read_frame().await {
-            assert_eq!(Some(Mode::Ingest), mode);
+        if let Ok(Recv::Started(session)) = connection.read_frame().await {
+            assert_eq!(Some(Mode::Ingest), session.mode);
         }
 
         connection
@@ -135,8 +757,8 @@ mod test {
 
         let mut connection = Connection::new(socket);
 
-        if let Ok(Recv::Connected(version)) = connection.read_frame().await {
-            assert_eq!("1.2.3".to_string(), version);
+        if let Ok(Recv::Connected(server)) = connection.read_frame().await {
+            assert_eq!(Version::new(1, 2, 3), server.version);
         }
 
         connection
@@ -144,8 +766,8 @@ mod test {
             .await
             .expect("Failed to send `START ingest`");
 
-        if let Ok(Recv::Started(mode, _size)) = connection.read_frame().await {
-            assert_eq!(Some(Mode::Search), mode);
+        if let Ok(Recv::Started(session)) = connection.read_frame().await {
+            assert_eq!(Some(Mode::Search), session.mode);
         }
 
         let query = Query::new(
@@ -169,4 +791,71 @@ mod test {
 
         if let Ok(Recv::Ended(_host)) = connection.read_frame().await {}
     }
+
+    #[tokio::test]
+    async fn connection_supports() {
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+
+        // Lines, as `TRIGGER` alone isn't a valid `Send`.
+        let server = tokio::spawn(async move {
+            let mut server = Framed::new(server, LinesCodec::new());
+            let mut lines = Vec::new();
+
+            for reply in &[
+                "RESULT commands(TRIGGER, INFO, PING, HELP, QUIT)\r\n",
+                "RESULT actions(consolidate)\r\n",
+                "OK\r\n",
+            ] {
+                match server.next().await {
+                    Some(Ok(line)) => lines.push(line),
+                    line => panic!("unexpected line; {:?}", line),
+                }
+                server
+                    .get_mut()
+                    .write_all(reply.as_bytes())
+                    .await
+                    .expect("Failed to send reply");
+            }
+
+            lines
+        });
+
+        let mut connection = Connection::new(client);
+
+        assert!(connection
+            .supports(&Send::Trigger(Trigger::Consolidate))
+            .await
+            .expect("Failed to check `TRIGGER consolidate`"));
+        assert!(!connection
+            .supports(&Send::Trigger(Trigger::Backup("2020-05-01".into())))
+            .await
+            .expect("Failed to check `TRIGGER backup`"));
+        assert!(connection
+            .supports(&Send::Info)
+            .await
+            .expect("Failed to check `INFO`"));
+
+        match connection
+            .write_supported(Send::Trigger(Trigger::Backup("2020-05-01".into())))
+            .await
+        {
+            Err(err) => assert_eq!("unsupported by server; `TRIGGER backup`", err.to_string()),
+            res => panic!("unexpected result; {:?}", res),
+        }
+
+        connection
+            .write_supported(Send::Trigger(Trigger::Consolidate))
+            .await
+            .expect("Failed to send `TRIGGER consolidate`");
+        assert_eq!(
+            Recv::Ok,
+            connection.read_frame().await.expect("Failed to read `OK`")
+        );
+
+        // Asked once.
+        assert_eq!(
+            vec!["HELP commands", "TRIGGER", "TRIGGER consolidate"],
+            server.await.expect("Failed to run server")
+        );
+    }
 }
@@ -0,0 +1,47 @@
+//! A fake Sonic server, for tests.
+
+use crate::frame::codec::ServerCodec;
+use crate::frame::recv::{Recv, ServerInfo, SessionInfo, Version};
+use crate::frame::send::Send;
+use crate::frame::Mode;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Framed;
+
+/// Greet with `CONNECTED`, then answer each frame with the next of
+/// `replies`, then drop `stream`. Returns the frames read.
+pub(crate) async fn serve<S>(stream: S, replies: Vec<Vec<Recv>>) -> Vec<Send>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut server = Framed::new(stream, ServerCodec::default());
+    let mut frames = Vec::new();
+
+    server
+        .send(Recv::Connected(ServerInfo {
+            version: Version::new(1, 2, 3),
+        }))
+        .await
+        .expect("Failed to send `CONNECTED`");
+
+    for reply in replies {
+        match server.next().await {
+            Some(Ok(frame)) => frames.push(frame),
+            frame => panic!("unexpected frame; {:?}", frame),
+        }
+        for frame in reply {
+            server.send(frame).await.expect("Failed to send reply");
+        }
+    }
+
+    frames
+}
+
+/// The reply to `START` on `mode`.
+pub(crate) fn started(mode: Mode) -> Vec<Recv> {
+    vec![Recv::Started(SessionInfo {
+        mode: Some(mode),
+        protocol: 1,
+        buffer_size: 20000,
+    })]
+}
@@ -0,0 +1,460 @@
+use bytes::{Buf, Bytes, BytesMut};
+use std::fmt;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::frame::recv::Recv;
+use crate::frame::send::Send;
+use crate::frame::{find_line, LineEnding, Parsing};
+
+/// Longest line read by default, `\r\n` excluded.
+/// Far above anything Sonic answers, it only guards against a runaway peer.
+pub const DEFAULT_MAX_LENGTH: usize = 1024 * 1024;
+
+// Past this length, a line leaves the read buffer reallocated to its usual size.
+const SHRINK_LENGTH: usize = 64 * 1024;
+const READ_CAPACITY: usize = 4 * 1024;
+
+/// A line longer than the maximum length was read.
+///
+/// The codec skips the line, the next one can still be decoded, but
+/// `Connection::read_frame` closes the stream.
+#[derive(Debug)]
+pub struct LineTooLong(pub usize);
+
+impl std::error::Error for LineTooLong {}
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "protocol error; line longer than {} bytes", self.0)
+    }
+}
+
+/// Client side of the protocol: reads `Recv` frames and writes `Send` frames.
+///
+/// Wrap any transport with `tokio_util::codec::Framed` to get a
+/// `Stream<Item = Result<Recv, Error>>` and a `Sink<Send>`.
+#[derive(Debug, Default)]
+pub struct ClientCodec {
+    parsing: Parsing,
+    lines: Lines,
+}
+
+impl ClientCodec {
+    pub fn new(parsing: Parsing) -> Self {
+        ClientCodec {
+            parsing,
+            lines: Lines::default(),
+        }
+    }
+
+    pub fn set_parsing(&mut self, parsing: Parsing) {
+        self.parsing = parsing;
+    }
+
+    /// Longest line read, `DEFAULT_MAX_LENGTH` by default.
+    pub fn max_length(&self) -> usize {
+        self.lines.max_length
+    }
+
+    /// Fail with `LineTooLong` on lines longer than `max_length` bytes,
+    /// instead of buffering them.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.lines.max_length = max_length;
+    }
+
+    /// Line endings read, `LineEnding::CrLf` by default.
+    pub fn set_line_ending(&mut self, ending: LineEnding) {
+        self.lines.ending = ending;
+    }
+}
+
+impl Decoder for ClientCodec {
+    type Item = Recv;
+    type Error = crate::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Recv>, Self::Error> {
+        match self.lines.next_line(src)? {
+            Some(line) => Ok(Some(Recv::parse_line(line, self.parsing)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Send> for ClientCodec {
+    type Error = crate::Error;
+
+    fn encode(&mut self, item: Send, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        item.validate()?;
+        item.encode(dst);
+        Ok(())
+    }
+}
+
+/// Server side of the protocol: reads `Send` frames and writes `Recv` frames.
+/// Useful for proxies and fake servers.
+#[derive(Debug, Default)]
+pub struct ServerCodec {
+    lines: Lines,
+}
+
+impl ServerCodec {
+    /// Longest line read, `DEFAULT_MAX_LENGTH` by default.
+    pub fn max_length(&self) -> usize {
+        self.lines.max_length
+    }
+
+    /// Fail with `LineTooLong` on lines longer than `max_length` bytes,
+    /// instead of buffering them.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.lines.max_length = max_length;
+    }
+
+    /// Line endings read, `LineEnding::CrLf` by default.
+    pub fn set_line_ending(&mut self, ending: LineEnding) {
+        self.lines.ending = ending;
+    }
+}
+
+impl Decoder for ServerCodec {
+    type Item = Send;
+    type Error = crate::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Send>, Self::Error> {
+        match self.lines.next_line(src)? {
+            Some(line) => Ok(Some(Send::parse_line(std::str::from_utf8(&line)?)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Recv> for ServerCodec {
+    type Error = crate::Error;
+
+    fn encode(&mut self, item: Recv, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        item.encode(dst);
+        Ok(())
+    }
+}
+
+/// Splits lines out of the read buffer, up to `max_length` bytes.
+#[derive(Debug)]
+struct Lines {
+    ending: LineEnding,
+    max_length: usize,
+    // Bytes of the buffer already scanned, without finding a line end.
+    searched: usize,
+    // Skipping the rest of a line too long, up to its end.
+    discarding: bool,
+}
+
+impl Default for Lines {
+    fn default() -> Self {
+        Lines {
+            ending: LineEnding::default(),
+            max_length: DEFAULT_MAX_LENGTH,
+            searched: 0,
+            discarding: false,
+        }
+    }
+}
+
+impl Lines {
+    /// Split the next complete line out of `src`, without its ending.
+    /// The line is consumed even if it then fails to parse, so the next one can be read.
+    fn next_line(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, crate::Error> {
+        if self.discarding && !self.discard(src) {
+            return Ok(None);
+        }
+
+        let (line_len, len) = match find_line(src, self.searched, self.ending) {
+            Some(found) => found,
+            // A trailing `\r` may still be part of the ending.
+            None if src.len() > self.max_length.saturating_add(1) => {
+                self.discarding = true;
+                self.discard(src);
+                return Err(LineTooLong(self.max_length).into());
+            }
+            None => {
+                self.searched = src.len();
+                return Ok(None);
+            }
+        };
+        self.searched = 0;
+
+        if line_len > self.max_length {
+            src.advance(len);
+            shrink(src);
+            return Err(LineTooLong(self.max_length).into());
+        }
+
+        let line = src.split_to(len).freeze().slice(..line_len);
+        if len > SHRINK_LENGTH {
+            shrink(src);
+        }
+
+        Ok(Some(line))
+    }
+
+    /// Drop `src` up to the end of the line being discarded.
+    /// Whether that end was found.
+    fn discard(&mut self, src: &mut BytesMut) -> bool {
+        self.searched = 0;
+
+        match find_line(src, 0, self.ending) {
+            Some((_, len)) => {
+                src.advance(len);
+                shrink(src);
+                self.discarding = false;
+                true
+            }
+            None => {
+                // Keep a trailing `\r`, its `\n` may come with the next read.
+                let keep = if src.ends_with(b"\r") { 1 } else { 0 };
+                src.advance(src.len() - keep);
+                shrink(src);
+                false
+            }
+        }
+    }
+}
+
+/// Move what is left of `src` into a buffer of the usual size,
+/// so the memory taken by a long line is freed once the line is.
+fn shrink(src: &mut BytesMut) {
+    let mut buf = BytesMut::with_capacity(READ_CAPACITY.max(src.len()));
+    buf.extend_from_slice(src);
+    *src = buf;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::frame::send::Query;
+    use crate::frame::text::Words;
+    use crate::frame::Mode;
+
+    #[test]
+    fn codec_decode_encode() {
+        let mut codec = ClientCodec::default();
+        let mut src = BytesMut::from(&b"PENDING Bt2m2gYa\r\nEVENT QUERY Bt2m2gYa conv"[..]);
+
+        assert_eq!(
+            Some(Recv::Pending("Bt2m2gYa".into())),
+            codec.decode(&mut src).expect("Failed to decode; `PENDING`")
+        );
+        assert_eq!(
+            None,
+            codec
+                .decode(&mut src)
+                .expect("Failed to decode; incomplete")
+        );
+
+        src.extend_from_slice(b"ersation:71f3d63b\r\nNOPE\r\nPONG\r\n");
+
+        assert_eq!(
+            Some(Recv::EventQuery(
+                "Bt2m2gYa".into(),
+                Words::from(vec!["conversation:71f3d63b"])
+            )),
+            codec
+                .decode(&mut src)
+                .expect("Failed to decode; `EVENT QUERY`")
+        );
+        assert!(codec.decode(&mut src).is_err());
+        assert_eq!(
+            Some(Recv::Pong),
+            codec.decode(&mut src).expect("Failed to decode; `PONG`")
+        );
+        assert!(src.is_empty());
+
+        let mut dst = BytesMut::new();
+        codec
+            .encode(Send::Ping, &mut dst)
+            .expect("Failed to encode; `PING`");
+        assert_eq!(&b"PING\r\n"[..], &dst[..]);
+
+        assert!(codec
+            .encode(
+                Send::Start(Mode::Search, "Secret Password".into()),
+                &mut dst
+            )
+            .is_err());
+        assert_eq!(&b"PING\r\n"[..], &dst[..]);
+
+        let mut server = ServerCodec::default();
+        let query = Send::Query(Query::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "valerian saliou".into(),
+        ));
+
+        assert_eq!(
+            Some(query),
+            server
+                .decode(&mut BytesMut::from(
+                    &b"QUERY messages user:0dcde3a6 \"valerian saliou\"\r\n"[..]
+                ))
+                .expect("Failed to decode; `QUERY`")
+        );
+
+        let mut dst = BytesMut::new();
+        server
+            .encode(Recv::Pending("Bt2m2gYa".into()), &mut dst)
+            .expect("Failed to encode; `PENDING`");
+        assert_eq!(&b"PENDING Bt2m2gYa\r\n"[..], &dst[..]);
+    }
+
+    #[test]
+    fn codec_max_length() {
+        let mut codec = ClientCodec::default();
+        codec.set_max_length(16);
+
+        // Too long, but not complete yet.
+        let mut src = BytesMut::from(&b"EVENT QUERY Bt2m2gYa conversation:71f3d63b\r"[..]);
+        assert!(codec
+            .decode(&mut src)
+            .expect_err("Failed to reject; incomplete")
+            .is::<LineTooLong>());
+        assert_eq!(&b"\r"[..], &src[..]);
+        assert_eq!(None, codec.decode(&mut src).expect("Failed to discard"));
+
+        // The rest of the line is skipped.
+        src.extend_from_slice(b"\nPONG\r\nEVENT QUERY Bt2m2gYa conv\r\nOK\r\n");
+        assert_eq!(
+            Some(Recv::Pong),
+            codec.decode(&mut src).expect("Failed to decode; `PONG`")
+        );
+
+        // Too long, and complete.
+        assert!(codec
+            .decode(&mut src)
+            .expect_err("Failed to reject; complete")
+            .is::<LineTooLong>());
+        assert_eq!(
+            Some(Recv::Ok),
+            codec.decode(&mut src).expect("Failed to decode; `OK`")
+        );
+
+        // Long lines, under the limit, leave a buffer of the usual size.
+        let mut codec = ClientCodec::default();
+        let mut src =
+            BytesMut::from(format!("PENDING {}\r\nPONG\r\n", "a".repeat(SHRINK_LENGTH)).as_bytes());
+        assert!(codec
+            .decode(&mut src)
+            .expect("Failed to decode; `PENDING`")
+            .is_some());
+        assert_eq!(&b"PONG\r\n"[..], &src[..]);
+        assert_eq!(READ_CAPACITY, src.capacity());
+    }
+
+    /// xorshift64*, seeded so a failure can be replayed.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 33) as usize % n
+        }
+
+        fn bytes(&mut self, alphabet: &[u8], max_len: usize) -> Vec<u8> {
+            (0..self.below(max_len + 1))
+                .map(|_| alphabet[self.below(alphabet.len())])
+                .collect()
+        }
+    }
+
+    /// The lines of `src` as `Lines` should read them, `None` if too long.
+    fn split_lines(src: &[u8], ending: LineEnding, max_length: usize) -> Vec<Option<Vec<u8>>> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        for i in 0..src.len() {
+            if src[i] != b'\n' {
+                continue;
+            }
+            let end = if i > start && src[i - 1] == b'\r' {
+                i - 1
+            } else if ending == LineEnding::Any {
+                i
+            } else {
+                continue;
+            };
+
+            let line = &src[start..end];
+            lines.push(Some(line.to_vec()).filter(|line| line.len() <= max_length));
+            start = i + 1;
+        }
+        if src.len() - start > max_length + 1 {
+            lines.push(None);
+        }
+
+        lines
+    }
+
+    #[test]
+    fn codec_random_chunks() {
+        let mut rng = Rng(0x5eed);
+
+        for _ in 0..2_000 {
+            let src = rng.bytes(b"ab \r\n\r\n", 120);
+            let ending = [LineEnding::CrLf, LineEnding::Any][rng.below(2)];
+            let max_length = [DEFAULT_MAX_LENGTH, rng.below(12)][rng.below(2)];
+
+            let mut lines = Lines {
+                ending,
+                max_length,
+                ..Lines::default()
+            };
+
+            let mut buf = BytesMut::new();
+            let mut read = Vec::new();
+            let mut rest = &src[..];
+            while !rest.is_empty() {
+                let (chunk, tail) = rest.split_at(1 + rng.below(rest.len().min(16)));
+                rest = tail;
+                buf.extend_from_slice(chunk);
+
+                loop {
+                    match lines.next_line(&mut buf) {
+                        Ok(Some(line)) => read.push(Some(line.to_vec())),
+                        Ok(None) => break,
+                        Err(err) => {
+                            assert!(err.is::<LineTooLong>(), "{}", err);
+                            read.push(None);
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(
+                split_lines(&src, ending, max_length),
+                read,
+                "{:?} {:?} {}",
+                String::from_utf8_lossy(&src),
+                ending,
+                max_length
+            );
+        }
+
+        // Anything goes, but nothing panics.
+        for _ in 0..2_000 {
+            let alphabet: Vec<u8> = (0..=255).collect();
+            let mut src = BytesMut::from(&rng.bytes(&alphabet, 200)[..]);
+            src.extend_from_slice(b"\r\n");
+
+            let mut client = ClientCodec::new(Parsing::Lenient);
+            let mut server = ServerCodec::default();
+            server.set_line_ending(LineEnding::Any);
+
+            let mut copy = src.clone();
+            while !src.is_empty() {
+                let _ = client.decode(&mut src);
+            }
+            while !copy.is_empty() {
+                let _ = server.decode(&mut copy);
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::frame::Error;
+
+/// Languages understood by Sonic, as ISO 639-3 codes.
+///
+/// Used as a `LANG()` hint on `QUERY` and `PUSH`, to select the stopwords
+/// and the tokenizer instead of letting the server detect them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Lang {
+    Afr,
+    Aka,
+    Amh,
+    Arb,
+    Aze,
+    Bel,
+    Ben,
+    Bul,
+    Cat,
+    Ces,
+    Cmn,
+    Dan,
+    Deu,
+    Ell,
+    Eng,
+    Epo,
+    Est,
+    Fin,
+    Fra,
+    Guj,
+    Heb,
+    Hin,
+    Hrv,
+    Hun,
+    Hye,
+    Ind,
+    Ita,
+    Jav,
+    Jpn,
+    Kan,
+    Kat,
+    Khm,
+    Kor,
+    Lat,
+    Lav,
+    Lit,
+    Mal,
+    Mar,
+    Mkd,
+    Mya,
+    Nep,
+    Nld,
+    Nob,
+    Ori,
+    Pan,
+    Pes,
+    Pol,
+    Por,
+    Ron,
+    Rus,
+    Sin,
+    Slk,
+    Slv,
+    Sna,
+    Spa,
+    Srp,
+    Swe,
+    Tam,
+    Tel,
+    Tgl,
+    Tha,
+    Tuk,
+    Tur,
+    Ukr,
+    Urd,
+    Uzb,
+    Vie,
+    Yid,
+    Zul,
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            Lang::Afr => "afr",
+            Lang::Aka => "aka",
+            Lang::Amh => "amh",
+            Lang::Arb => "arb",
+            Lang::Aze => "aze",
+            Lang::Bel => "bel",
+            Lang::Ben => "ben",
+            Lang::Bul => "bul",
+            Lang::Cat => "cat",
+            Lang::Ces => "ces",
+            Lang::Cmn => "cmn",
+            Lang::Dan => "dan",
+            Lang::Deu => "deu",
+            Lang::Ell => "ell",
+            Lang::Eng => "eng",
+            Lang::Epo => "epo",
+            Lang::Est => "est",
+            Lang::Fin => "fin",
+            Lang::Fra => "fra",
+            Lang::Guj => "guj",
+            Lang::Heb => "heb",
+            Lang::Hin => "hin",
+            Lang::Hrv => "hrv",
+            Lang::Hun => "hun",
+            Lang::Hye => "hye",
+            Lang::Ind => "ind",
+            Lang::Ita => "ita",
+            Lang::Jav => "jav",
+            Lang::Jpn => "jpn",
+            Lang::Kan => "kan",
+            Lang::Kat => "kat",
+            Lang::Khm => "khm",
+            Lang::Kor => "kor",
+            Lang::Lat => "lat",
+            Lang::Lav => "lav",
+            Lang::Lit => "lit",
+            Lang::Mal => "mal",
+            Lang::Mar => "mar",
+            Lang::Mkd => "mkd",
+            Lang::Mya => "mya",
+            Lang::Nep => "nep",
+            Lang::Nld => "nld",
+            Lang::Nob => "nob",
+            Lang::Ori => "ori",
+            Lang::Pan => "pan",
+            Lang::Pes => "pes",
+            Lang::Pol => "pol",
+            Lang::Por => "por",
+            Lang::Ron => "ron",
+            Lang::Rus => "rus",
+            Lang::Sin => "sin",
+            Lang::Slk => "slk",
+            Lang::Slv => "slv",
+            Lang::Sna => "sna",
+            Lang::Spa => "spa",
+            Lang::Srp => "srp",
+            Lang::Swe => "swe",
+            Lang::Tam => "tam",
+            Lang::Tel => "tel",
+            Lang::Tgl => "tgl",
+            Lang::Tha => "tha",
+            Lang::Tuk => "tuk",
+            Lang::Tur => "tur",
+            Lang::Ukr => "ukr",
+            Lang::Urd => "urd",
+            Lang::Uzb => "uzb",
+            Lang::Vie => "vie",
+            Lang::Yid => "yid",
+            Lang::Zul => "zul",
+        };
+        fmt.write_str(code)
+    }
+}
+
+impl FromStr for Lang {
+    type Err = Error;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "afr" => Ok(Lang::Afr),
+            "aka" => Ok(Lang::Aka),
+            "amh" => Ok(Lang::Amh),
+            "arb" => Ok(Lang::Arb),
+            "aze" => Ok(Lang::Aze),
+            "bel" => Ok(Lang::Bel),
+            "ben" => Ok(Lang::Ben),
+            "bul" => Ok(Lang::Bul),
+            "cat" => Ok(Lang::Cat),
+            "ces" => Ok(Lang::Ces),
+            "cmn" => Ok(Lang::Cmn),
+            "dan" => Ok(Lang::Dan),
+            "deu" => Ok(Lang::Deu),
+            "ell" => Ok(Lang::Ell),
+            "eng" => Ok(Lang::Eng),
+            "epo" => Ok(Lang::Epo),
+            "est" => Ok(Lang::Est),
+            "fin" => Ok(Lang::Fin),
+            "fra" => Ok(Lang::Fra),
+            "guj" => Ok(Lang::Guj),
+            "heb" => Ok(Lang::Heb),
+            "hin" => Ok(Lang::Hin),
+            "hrv" => Ok(Lang::Hrv),
+            "hun" => Ok(Lang::Hun),
+            "hye" => Ok(Lang::Hye),
+            "ind" => Ok(Lang::Ind),
+            "ita" => Ok(Lang::Ita),
+            "jav" => Ok(Lang::Jav),
+            "jpn" => Ok(Lang::Jpn),
+            "kan" => Ok(Lang::Kan),
+            "kat" => Ok(Lang::Kat),
+            "khm" => Ok(Lang::Khm),
+            "kor" => Ok(Lang::Kor),
+            "lat" => Ok(Lang::Lat),
+            "lav" => Ok(Lang::Lav),
+            "lit" => Ok(Lang::Lit),
+            "mal" => Ok(Lang::Mal),
+            "mar" => Ok(Lang::Mar),
+            "mkd" => Ok(Lang::Mkd),
+            "mya" => Ok(Lang::Mya),
+            "nep" => Ok(Lang::Nep),
+            "nld" => Ok(Lang::Nld),
+            "nob" => Ok(Lang::Nob),
+            "ori" => Ok(Lang::Ori),
+            "pan" => Ok(Lang::Pan),
+            "pes" => Ok(Lang::Pes),
+            "pol" => Ok(Lang::Pol),
+            "por" => Ok(Lang::Por),
+            "ron" => Ok(Lang::Ron),
+            "rus" => Ok(Lang::Rus),
+            "sin" => Ok(Lang::Sin),
+            "slk" => Ok(Lang::Slk),
+            "slv" => Ok(Lang::Slv),
+            "sna" => Ok(Lang::Sna),
+            "spa" => Ok(Lang::Spa),
+            "srp" => Ok(Lang::Srp),
+            "swe" => Ok(Lang::Swe),
+            "tam" => Ok(Lang::Tam),
+            "tel" => Ok(Lang::Tel),
+            "tgl" => Ok(Lang::Tgl),
+            "tha" => Ok(Lang::Tha),
+            "tuk" => Ok(Lang::Tuk),
+            "tur" => Ok(Lang::Tur),
+            "ukr" => Ok(Lang::Ukr),
+            "urd" => Ok(Lang::Urd),
+            "uzb" => Ok(Lang::Uzb),
+            "vie" => Ok(Lang::Vie),
+            "yid" => Ok(Lang::Yid),
+            "zul" => Ok(Lang::Zul),
+            _ => Err(format!("invalid frame; unknown lang `{}`", code).into()),
+        }
+    }
+}
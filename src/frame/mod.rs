@@ -1,27 +1,102 @@
+//! Sonic frames: `send::Send` for commands, `recv::Recv` for replies.
+//!
+//! # Serde
+//!
+//! With the `serde` feature, every frame type implements `Serialize` and
+//! `Deserialize`, e.g. to keep protocol exchanges as JSON lines. The schema
+//! is part of the API, it only changes with the major version:
+//!
+//! - Enums are externally tagged, with `snake_case` variant names: unit
+//!   variants are strings, others are objects with a single key, holding an
+//!   array when the variant holds more than one value.
+//! - Builders (`Query`, `Push`, ...) are objects with one key per argument,
+//!   as named by their constructor. Absent options are `null`, and may be
+//!   left out when deserializing.
+//! - `Mode` and `Lang` are their wire codes, e.g. `"search"` and `"eng"`.
+//!   `ErrorKind` is the error code, or `{"unknown": code}`.
+//! - Ids (`Text`) are strings, and `EVENT` words (`Words`) lists of strings.
+//!
+//! ```text
+//! "ping"
+//! {"start":["search","SecretPassword"]}
+//! {"query":{"collection":"messages","bucket":"user:0dcde3a6","terms":"valerian","limit":10,"offset":null,"lang":"eng"}}
+//! {"trigger":{"backup":"2020-05-01"}}
+//! {"pending":"Bt2m2gYa"}
+//! {"event_query":["Bt2m2gYa",["conversation:71f3d63b"]]}
+//! {"result":3}
+//! {"err":{"kind":"query_limit","detail":null}}
+//! ```
+//!
+//! The `START` password is kept as is, so that exchanges can be replayed.
+
+use memchr::memchr;
 use std::fmt;
+use std::io::Cursor;
 use std::num::ParseIntError;
+use std::str::FromStr;
+use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 
+pub mod codec;
+pub mod lang;
 pub mod recv;
 pub mod send;
+pub mod text;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Mode {
     Search,
     Ingest,
+    Control,
 }
 
-impl ToString for Mode {
-    fn to_string(&self) -> String {
+impl fmt::Display for Mode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Mode::Ingest => "ingest".to_string(),
-            Mode::Search => "search".to_string(),
+            Mode::Ingest => fmt.write_str("ingest"),
+            Mode::Search => fmt.write_str("search"),
+            Mode::Control => fmt.write_str("control"),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = Error;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "search" => Ok(Mode::Search),
+            "ingest" => Ok(Mode::Ingest),
+            "control" => Ok(Mode::Control),
+            _ => Err(format!("invalid frame; unknown mode `{}`", mode).into()),
         }
     }
 }
 
+/// How lines that aren't part of the protocol are read.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Parsing {
+    /// Fail with a protocol error, holding the line.
+    #[default]
+    Strict,
+    /// Read them as `Recv::Unknown`, holding the line.
+    Lenient,
+}
+
+/// Which line endings are read.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LineEnding {
+    /// `\r\n` only, as sent by Sonic. A bare `\n` is part of the line.
+    #[default]
+    CrLf,
+    /// `\r\n` or a bare `\n`, e.g. from a hand-written transcript.
+    Any,
+}
+
 #[derive(Debug)]
-pub(crate) enum Error {
+pub enum Error {
     Incomplete,
     Other(crate::Error),
 }
@@ -32,6 +107,12 @@ impl From<FromUtf8Error> for Error {
     }
 }
 
+impl From<Utf8Error> for Error {
+    fn from(_src: Utf8Error) -> Error {
+        "protocol error; invalid frame format".into()
+    }
+}
+
 impl From<String> for Error {
     fn from(src: String) -> Error {
         Error::Other(src.into())
@@ -59,3 +140,125 @@ impl fmt::Display for Error {
         }
     }
 }
+
+/// Split the `key(value) key(value) ...` list found on `RESULT` lines,
+/// and as `LIMIT(10) OFFSET(5)` modifiers.
+/// Values may contain spaces, e.g. `commands(QUERY, SUGGEST)`.
+pub(crate) fn key_values(src: &str) -> Result<Vec<(&str, &str)>, Error> {
+    let mut pairs = Vec::new();
+    let mut rest = src.trim_start();
+
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or("invalid frame; `key(value)` key")?;
+        let key = &rest[..open];
+
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err("invalid frame; `key(value)` key".into());
+        }
+
+        let mut depth = 0;
+        let mut close = None;
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close = close.ok_or("invalid frame; `key(value)` value")?;
+
+        pairs.push((key, &rest[open + 1..close]));
+        rest = rest[close + 1..].trim_start();
+    }
+
+    if pairs.is_empty() {
+        return Err("invalid frame; `key(value)`".into());
+    }
+
+    Ok(pairs)
+}
+
+/// Try to get a line ('\r\n') from the Cursor.
+/// If it isn't possible, `return` frame `Incomplete`.
+pub(crate) fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
+    let start = src.position() as usize;
+    let buf: &'a [u8] = src.get_ref().get(start..).unwrap_or_default();
+
+    match find_line(buf, 0, LineEnding::CrLf) {
+        Some((line_len, len)) => {
+            src.set_position((start + len) as u64);
+
+            Ok(&buf[..line_len])
+        }
+        None => Err(Error::Incomplete),
+    }
+}
+
+/// Find the end of the line `buf` starts with, scanning from `from`:
+/// the bytes before it are known not to hold the end, so a line
+/// arriving in many reads is only scanned once.
+///
+/// Returns the length of the line, then of the line with its ending.
+/// A `\r` ending `buf` may be followed by `\n` on the next read.
+pub(crate) fn find_line(buf: &[u8], from: usize, ending: LineEnding) -> Option<(usize, usize)> {
+    let mut from = from;
+
+    while let Some(i) = memchr(b'\n', buf.get(from..)?) {
+        let i = from + i;
+
+        if i > 0 && buf[i - 1] == b'\r' {
+            return Some((i - 1, i + 1));
+        }
+        if ending == LineEnding::Any {
+            return Some((i, i + 1));
+        }
+
+        from = i + 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frame_get_line() {
+        for (src, line, position) in &[
+            (&b"PONG\r\n"[..], Some(&b"PONG"[..]), 6),
+            (b"\r\nPONG\r\n", Some(b""), 2),
+            (b"PO\rNG\r\r\n", Some(b"PO\rNG\r"), 8),
+            (b"PO\nNG\r\n", Some(b"PO\nNG"), 7),
+            (b"PONG\r", None, 0),
+            (b"PONG\n", None, 0),
+            (b"\r", None, 0),
+            (b"\n", None, 0),
+            (b"", None, 0),
+        ] {
+            let mut cursor = Cursor::new(*src);
+            match line {
+                Some(line) => assert_eq!(*line, get_line(&mut cursor).expect("Failed to get line")),
+                None => assert!(get_line(&mut cursor).is_err()),
+            }
+            assert_eq!(*position, cursor.position());
+        }
+
+        // Past the end of the buffer.
+        let mut cursor: Cursor<&[u8]> = Cursor::new(b"PONG\r\n");
+        cursor.set_position(10);
+        assert!(get_line(&mut cursor).is_err());
+
+        assert_eq!(Some((4, 5)), find_line(b"PONG\nOK", 0, LineEnding::Any));
+        assert_eq!(Some((4, 6)), find_line(b"PONG\r\nOK", 0, LineEnding::Any));
+        assert_eq!(Some((5, 7)), find_line(b"PO\nNG\r\n", 3, LineEnding::CrLf));
+        assert_eq!(None, find_line(b"PONG\r", 0, LineEnding::Any));
+        assert_eq!(None, find_line(b"PONG", 10, LineEnding::Any));
+    }
+}
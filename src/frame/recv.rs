@@ -1,168 +1,521 @@
-use regex::Regex;
+use bytes::{Bytes, BytesMut};
+use memchr::memchr;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
 use std::io::Cursor;
+use std::str::FromStr;
 
-use crate::frame::{Error, Mode};
+use crate::frame::text::{Text, Words};
+use crate::frame::{get_line, key_values, Error, Mode, Parsing};
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Recv {
-    Connected(String),
-    // TODO
-    //      (mode,        buffer_size)
-    // Use buffer_size - admission control.
-    Started(Option<Mode>, u64),
-    Pending(String),
+    Connected(ServerInfo),
+    Started(SessionInfo),
+    Pending(Text),
     Ok,
     Pong,
-    EventQuery(String, Vec<String>),
-    EventSuggest(String, Vec<String>),
+    EventQuery(Text, Words),
+    EventSuggest(Text, Words),
+    EventList(Text, Words),
+    Result(u64),
+    // Answer to `HELP commands`.
+    Commands(Capabilities),
+    // `key(value)` results other than `INFO` and `HELP`.
+    ResultPairs(Vec<(String, String)>),
+    Info(Info),
     Ended(String),
-    Err(String),
+    Err(ServerError),
+    // A line this client doesn't know, only on `Parsing::Lenient`.
+    Unknown(String),
+}
+
+/// An `ERR` reply, e.g. `ERR invalid_format(PUSH <collection> ...)`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerError {
+    pub kind: ErrorKind,
+    /// What is between parentheses, if any.
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ErrorKind {
+    InvalidFormat,
+    BufferOverflow,
+    UnknownCommand,
+    NotRecognized,
+    QueryLimit,
+    NotFound,
+    QueryError,
+    InternalError,
+    ShuttingDown,
+    PolicyReject,
+    InvalidMetaKey,
+    InvalidMetaValue,
+    InvalidMode,
+    AuthenticationRequired,
+    AuthenticationFailed,
+    Unknown(String),
+}
+
+impl ErrorKind {
+    fn from_code(code: &str) -> Self {
+        match code {
+            "invalid_format" => ErrorKind::InvalidFormat,
+            "buffer_overflow" => ErrorKind::BufferOverflow,
+            "unknown_command" => ErrorKind::UnknownCommand,
+            "not_recognized" => ErrorKind::NotRecognized,
+            "query_limit" => ErrorKind::QueryLimit,
+            "not_found" => ErrorKind::NotFound,
+            "query_error" => ErrorKind::QueryError,
+            "internal_error" => ErrorKind::InternalError,
+            "shutting_down" => ErrorKind::ShuttingDown,
+            "policy_reject" => ErrorKind::PolicyReject,
+            "invalid_meta_key" => ErrorKind::InvalidMetaKey,
+            "invalid_meta_value" => ErrorKind::InvalidMetaValue,
+            "invalid_mode" => ErrorKind::InvalidMode,
+            "authentication_required" => ErrorKind::AuthenticationRequired,
+            "authentication_failed" => ErrorKind::AuthenticationFailed,
+            code => ErrorKind::Unknown(code.to_string()),
+        }
+    }
+
+    /// The code as sent by the server.
+    pub fn code(&self) -> &str {
+        match self {
+            ErrorKind::InvalidFormat => "invalid_format",
+            ErrorKind::BufferOverflow => "buffer_overflow",
+            ErrorKind::UnknownCommand => "unknown_command",
+            ErrorKind::NotRecognized => "not_recognized",
+            ErrorKind::QueryLimit => "query_limit",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::QueryError => "query_error",
+            ErrorKind::InternalError => "internal_error",
+            ErrorKind::ShuttingDown => "shutting_down",
+            ErrorKind::PolicyReject => "policy_reject",
+            ErrorKind::InvalidMetaKey => "invalid_meta_key",
+            ErrorKind::InvalidMetaValue => "invalid_meta_value",
+            ErrorKind::InvalidMode => "invalid_mode",
+            ErrorKind::AuthenticationRequired => "authentication_required",
+            ErrorKind::AuthenticationFailed => "authentication_failed",
+            ErrorKind::Unknown(code) => code,
+        }
+    }
+}
+
+impl ServerError {
+    fn parse(src: &str) -> Self {
+        match src.find('(') {
+            Some(open) if src.ends_with(')') => ServerError {
+                kind: ErrorKind::from_code(&src[..open]),
+                detail: Some(src[open + 1..src.len() - 1].to_string()),
+            },
+            _ => ServerError {
+                kind: ErrorKind::from_code(src),
+                detail: None,
+            },
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+impl fmt::Display for ServerError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(fmt, "server error; {}({})", self.kind.code(), detail),
+            None => write!(fmt, "server error; {}", self.kind.code()),
+        }
+    }
+}
+
+/// A Sonic server version, e.g. `1.10.2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let mut numbers = version.split('.');
+        let mut number = || -> Result<u64, Error> {
+            let number = numbers.next().ok_or("invalid frame; version")?;
+            Ok(number.parse()?)
+        };
+
+        let version = Version {
+            major: number()?,
+            minor: number()?,
+            patch: number()?,
+        };
+
+        if numbers.next().is_some() {
+            return Err("invalid frame; version".into());
+        }
+
+        Ok(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Read from `CONNECTED <sonic-server v1.2.3>`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerInfo {
+    pub version: Version,
+}
+
+/// Read from `STARTED search protocol(1) buffer(20000)`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionInfo {
+    pub mode: Option<Mode>,
+    pub protocol: u64,
+    /// The longest line, in bytes, the server accepts.
+    pub buffer_size: u64,
+}
+
+/// Commands available on the current mode, answered to `HELP commands`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    pub commands: BTreeSet<String>,
+    /// Actions of the commands taking one, as answered to the command
+    /// alone, e.g. `RESULT actions(consolidate, backup, restore)` to `TRIGGER`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub actions: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Capabilities {
+    /// Whether `command` (e.g. `"LIST"`) is available.
+    pub fn contains(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+
+    /// Whether `action` of `command` (e.g. `"TRIGGER"`, `"backup"`) is available.
+    /// Any action is, if the actions of `command` weren't asked.
+    pub fn contains_action(&self, command: &str, action: &str) -> bool {
+        match self.actions.get(command) {
+            Some(actions) => self.contains(command) && actions.contains(action),
+            None => self.contains(command),
+        }
+    }
+
+    /// Record `actions`, the list answered to `command` alone.
+    pub(crate) fn set_actions(&mut self, command: &str, actions: &str) {
+        self.actions.insert(command.to_string(), names(actions));
+    }
+}
+
+/// Split a `commands(...)` or `actions(...)` list.
+fn names(list: &str) -> BTreeSet<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// A `RESULT` with any of them answers `INFO`.
+const INFO_KEYS: [&str; 8] = [
+    "uptime",
+    "clients_connected",
+    "commands_total",
+    "command_latency_best",
+    "command_latency_worst",
+    "kv_open_count",
+    "fst_open_count",
+    "fst_consolidate_count",
+];
+
+/// Server statistics, answered to `INFO` on `control` mode.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Info {
+    pub uptime: u64,
+    pub clients_connected: u64,
+    pub commands_total: u64,
+    pub command_latency_best: u64,
+    pub command_latency_worst: u64,
+    pub kv_open_count: u64,
+    pub fst_open_count: u64,
+    pub fst_consolidate_count: u64,
 }
 
 impl Recv {
-    pub(crate) fn parse(src: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        match get_line(src) {
-            Ok(line) => {
-                let line = String::from_utf8(line.to_vec())?;
-                let mut words = line.split_whitespace();
-
-                if let Some(word) = words.next() {
-                    match word {
-                        "CONNECTED" => {
-                            let sonic_version = words.next().ok_or("invalid frame; `CONNECTED`")?;
-
-                            if sonic_version != "<sonic-server" {
-                                return Err("invalid frame; `CONNECTED`".into());
-                            }
-
-                            let version = words.next().ok_or("invalid frame; `CONNECTED`")?;
-
-                            lazy_static! {
-                                static ref RE_CONNECTED: Regex =
-                                    Regex::new(r"v\d\.\d\.\d>").expect("Failed to create Regex");
-                            }
-
-                            if RE_CONNECTED.is_match(version) {
-                                let version: Vec<&str> =
-                                    version.split(|c| c == 'v' || c == '>').collect();
-                                return Ok(Recv::Connected(version[1].to_string()));
-                            }
-
-                            return Err("invalid frame; `CONNECTED`".into());
-                        }
-                        "STARTED" => {
-                            let mode = words.next().ok_or("invalid frame; `STARTED` mode")?;
-                            let mode = if mode == "search" {
-                                Some(Mode::Search)
-                            } else if mode == "ingest" {
-                                Some(Mode::Ingest)
-                            } else {
-                                None
-                            };
-
-                            let _prot_version =
-                                words.next().ok_or("invalid frame; `STARTED` proto")?;
-
-                            let b_size = words.next().ok_or("invalid frame; `STARTED` b_size")?;
-
-                            lazy_static! {
-                                static ref RE_STARTED: Regex =
-                                    Regex::new(r"buffer(\d*)").expect("Failed to create Regex");
-                            };
-
-                            if RE_STARTED.is_match(b_size) {
-                                let b_size: Vec<&str> =
-                                    b_size.split(|c| c == '(' || c == ')').collect();
-                                let size = b_size.get(1).ok_or("invalid frame; `STARTED` size")?;
-                                let size = size.parse::<u64>()?;
-
-                                return Ok(Recv::Started(mode, size));
-                            }
-
-                            return Err(format!(
-                                "invalid frame; `STARTED` not is_match {}",
-                                b_size
-                            )
-                            .into());
-                        }
-
-                        "PENDING" => {
-                            let id = words.next().ok_or("invalid frame; `PENDING`")?;
-                            return Ok(Recv::Pending(id.to_string()));
-                        }
-                        "EVENT" => {
-                            let event_type = words.next().ok_or("invalid frame; `EVENT` type")?;
-                            if event_type == "QUERY" {
-                                let id = words.next().ok_or("invalid frame; `EVENT` id")?;
-
-                                let keys = words.map(|word| word.to_string()).collect();
-
-                                return Ok(Recv::EventQuery(id.to_string(), keys));
-                            }
-
-                            if event_type == "SUGGEST" {
-                                let id = words.next().ok_or("invalid frame; `EVENT` id")?;
-
-                                let suggestions = words.map(|word| word.to_string()).collect();
-
-                                return Ok(Recv::EventSuggest(id.to_string(), suggestions));
-                            }
-
-                            return Err("invalid frame; `EVENT` final".into());
-                        }
-                        "OK" => return Ok(Recv::Ok),
-                        "PONG" => return Ok(Recv::Pong),
-
-                        "ENDED" => {
-                            let quit = words.next().ok_or("invalid frame; `ENDED`")?;
-                            return Ok(Recv::Ended(quit.to_string()));
-                        }
-                        "ERR" => {
-                            let err_vec: Vec<&str> = words.collect();
-                            let mut err_str = String::from("");
-                            for word in err_vec {
-                                err_str.push_str(&format!(" {}", word));
-                            }
-                            return Ok(Recv::Err(err_str.to_string()));
-                        }
-                        _ => return Ok(Recv::Pending(word.to_string())), // _ => return Err("error protocol; invalid command".into()),
-                    }
-                } else {
-                    return Err("error protocol; invalid frame".into());
+    /// Read a server line, strictly.
+    pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Self, Error> {
+        Recv::parse_with(src, Parsing::default())
+    }
+
+    /// Read a server line, as written by `encode`.
+    pub fn parse_with(src: &mut Cursor<&[u8]>, parsing: Parsing) -> Result<Self, Error> {
+        let line = get_line(src)?;
+        Recv::parse_line(Bytes::copy_from_slice(line), parsing)
+    }
+
+    /// Read a server `line`, without its `\r\n`.
+    ///
+    /// Ids and words are slices of `line`, nothing is copied on the
+    /// `PENDING` and `EVENT` hot path.
+    pub(crate) fn parse_line(line: Bytes, parsing: Parsing) -> Result<Self, Error> {
+        let text = std::str::from_utf8(&line)?;
+        let (word, rest) = next_word(text);
+
+        match word {
+            "CONNECTED" => {
+                let (server, rest) = next_word(rest);
+                let (version, _) = next_word(rest);
+
+                if server != "<sonic-server" {
+                    return Err("invalid frame; `CONNECTED`".into());
+                }
+
+                let version = version
+                    .strip_prefix('v')
+                    .and_then(|version| version.strip_suffix('>'))
+                    .ok_or("invalid frame; `CONNECTED`")?
+                    .parse()?;
+
+                Ok(Recv::Connected(ServerInfo { version }))
+            }
+            "STARTED" => {
+                let (mode, rest) = next_word(rest);
+                let (protocol, rest) = next_word(rest);
+                let (buffer_size, _) = next_word(rest);
+
+                let protocol =
+                    paren(protocol, "protocol").ok_or("invalid frame; `STARTED` proto")?;
+                let buffer_size =
+                    paren(buffer_size, "buffer").ok_or("invalid frame; `STARTED` b_size")?;
+
+                Ok(Recv::Started(SessionInfo {
+                    mode: mode.parse().ok(),
+                    protocol: protocol.parse()?,
+                    buffer_size: buffer_size.parse()?,
+                }))
+            }
+            "PENDING" => {
+                let (id, _) = next_word(rest);
+
+                if id.is_empty() {
+                    return Err("invalid frame; `PENDING`".into());
+                }
+
+                Ok(Recv::Pending(Text::slice_of(&line, id)))
+            }
+            "EVENT" => {
+                let (event_type, rest) = next_word(rest);
+                let (id, rest) = next_word(rest);
+
+                if id.is_empty() {
+                    return Err("invalid frame; `EVENT` id".into());
+                }
+
+                let id = Text::slice_of(&line, id);
+                let words = Words::slice_of(&line, rest);
+
+                match event_type {
+                    "QUERY" => Ok(Recv::EventQuery(id, words)),
+                    "SUGGEST" => Ok(Recv::EventSuggest(id, words)),
+                    "LIST" => Ok(Recv::EventList(id, words)),
+                    _ => unknown(text, parsing),
+                }
+            }
+            "RESULT" => {
+                let rest = rest.trim();
+
+                if let Ok(count) = rest.parse::<u64>() {
+                    return Ok(Recv::Result(count));
+                }
+
+                let pairs = key_values(rest)?;
+
+                if let [("commands", commands)] = pairs.as_slice() {
+                    return Ok(Recv::Commands(Capabilities {
+                        commands: names(commands),
+                        actions: BTreeMap::new(),
+                    }));
+                }
+
+                if !pairs.iter().any(|(key, _)| INFO_KEYS.contains(key)) {
+                    let pairs = pairs
+                        .into_iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect();
+
+                    return Ok(Recv::ResultPairs(pairs));
                 }
+
+                let mut info = Info::default();
+
+                for (key, value) in pairs {
+                    let field = match key {
+                        "uptime" => &mut info.uptime,
+                        "clients_connected" => &mut info.clients_connected,
+                        "commands_total" => &mut info.commands_total,
+                        "command_latency_best" => &mut info.command_latency_best,
+                        "command_latency_worst" => &mut info.command_latency_worst,
+                        "kv_open_count" => &mut info.kv_open_count,
+                        "fst_open_count" => &mut info.fst_open_count,
+                        "fst_consolidate_count" => &mut info.fst_consolidate_count,
+                        // Newer servers may report more statistics, of any kind.
+                        _ => continue,
+                    };
+
+                    *field = value.parse()?;
+                }
+
+                Ok(Recv::Info(info))
             }
-            Err(e) => Err(e),
+            "OK" => Ok(Recv::Ok),
+            "PONG" => Ok(Recv::Pong),
+            "ENDED" => {
+                let (quit, _) = next_word(rest);
+
+                if quit.is_empty() {
+                    return Err("invalid frame; `ENDED`".into());
+                }
+
+                Ok(Recv::Ended(quit.to_string()))
+            }
+            "ERR" => Ok(Recv::Err(ServerError::parse(rest.trim()))),
+            _ => unknown(text, parsing),
         }
     }
+}
 
-    /// Check if it is possible to read a line.
-    pub(crate) fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
-        match get_line(src) {
-            Ok(_) => Ok(()),
-            Err(Error::Incomplete) => Err(Error::Incomplete),
-            _ => Err("invalid protocol".into()),
-        }
+/// Split the first word out of `src`, from the rest after its space.
+fn next_word(src: &str) -> (&str, &str) {
+    let src = src.trim_start_matches(' ');
+
+    match memchr(b' ', src.as_bytes()) {
+        Some(end) => (&src[..end], &src[end + 1..]),
+        None => (src, ""),
     }
 }
 
-/// Try to get a line ('\r\n') from the Cursor.
-/// If it isn't possible, `return` frame `Incomplete`.
-fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
-    let start = src.position() as usize;
-    let end = src.get_ref().len() as usize;
+/// The value of a `key(value)` word.
+fn paren<'a>(word: &'a str, key: &str) -> Option<&'a str> {
+    word.strip_prefix(key)?.strip_prefix('(')?.strip_suffix(')')
+}
+
+/// A line that isn't part of the protocol, as far as this client knows.
+fn unknown(line: &str, parsing: Parsing) -> Result<Recv, Error> {
+    match parsing {
+        Parsing::Strict => Err(format!("protocol error; unknown frame `{}`", line).into()),
+        Parsing::Lenient => Ok(Recv::Unknown(line.to_string())),
+    }
+}
 
-    for i in start..end {
-        if src.get_ref()[i] == b'\r' && src.get_ref()[i + 1] == b'\n' {
-            src.set_position((i + 2) as u64);
+impl Recv {
+    /// Write the line, `\r\n` included, at the end of `dst`.
+    pub fn encode(&self, dst: &mut BytesMut) {
+        // Writing into a `BytesMut` never fails, it grows as needed.
+        let _ = write!(dst, "{}", self);
+    }
+}
 
-            return Ok(&src.get_ref()[start..i]);
+/// The line, `\r\n` included, in the server wire format.
+impl fmt::Display for Recv {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recv::Connected(server) => {
+                write!(fmt, "CONNECTED <sonic-server v{}>\r\n", server.version)
+            }
+            Recv::Started(session) => {
+                match session.mode {
+                    Some(mode) => write!(fmt, "STARTED {}", mode)?,
+                    None => fmt.write_str("STARTED unknown")?,
+                };
+                write!(
+                    fmt,
+                    " protocol({}) buffer({})\r\n",
+                    session.protocol, session.buffer_size
+                )
+            }
+            Recv::Pending(id) => write!(fmt, "PENDING {}\r\n", id),
+            Recv::Ok => fmt.write_str("OK\r\n"),
+            Recv::Pong => fmt.write_str("PONG\r\n"),
+            Recv::EventQuery(id, keys) => event(fmt, "QUERY", id, keys),
+            Recv::EventSuggest(id, suggestions) => event(fmt, "SUGGEST", id, suggestions),
+            Recv::EventList(id, list) => event(fmt, "LIST", id, list),
+            Recv::Result(count) => write!(fmt, "RESULT {}\r\n", count),
+            Recv::Commands(capabilities) => {
+                fmt.write_str("RESULT commands(")?;
+                for (i, command) in capabilities.commands.iter().enumerate() {
+                    if i > 0 {
+                        fmt.write_str(", ")?;
+                    }
+                    fmt.write_str(command)?;
+                }
+                fmt.write_str(")\r\n")
+            }
+            Recv::ResultPairs(pairs) => {
+                fmt.write_str("RESULT")?;
+                for (key, value) in pairs {
+                    write!(fmt, " {}({})", key, value)?;
+                }
+                fmt.write_str("\r\n")
+            }
+            Recv::Info(info) => write!(
+                fmt,
+                "RESULT uptime({}) clients_connected({}) commands_total({}) \
+                 command_latency_best({}) command_latency_worst({}) kv_open_count({}) \
+                 fst_open_count({}) fst_consolidate_count({})\r\n",
+                info.uptime,
+                info.clients_connected,
+                info.commands_total,
+                info.command_latency_best,
+                info.command_latency_worst,
+                info.kv_open_count,
+                info.fst_open_count,
+                info.fst_consolidate_count
+            ),
+            Recv::Ended(reason) => write!(fmt, "ENDED {}\r\n", reason),
+            Recv::Err(err) => match &err.detail {
+                Some(detail) => write!(fmt, "ERR {}({})\r\n", err.kind.code(), detail),
+                None => write!(fmt, "ERR {}\r\n", err.kind.code()),
+            },
+            Recv::Unknown(line) => write!(fmt, "{}\r\n", line),
         }
     }
+}
 
-    Err(Error::Incomplete)
+fn event(fmt: &mut fmt::Formatter, kind: &str, id: &Text, words: &Words) -> fmt::Result {
+    write!(fmt, "EVENT {} {}", kind, id)?;
+    for word in words.iter() {
+        write!(fmt, " {}", word)?;
+    }
+    fmt.write_str("\r\n")
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -171,14 +524,31 @@ mod test {
         let mut line: Cursor<&[u8]> = Cursor::new(b"CONNECTED <sonic-server v1.0.0>\r\n");
 
         assert_eq!(
-            Recv::Connected("1.0.0".to_string()),
+            Recv::Connected(ServerInfo {
+                version: Version::new(1, 0, 0)
+            }),
             Recv::parse(&mut line).expect("Failed to parse; `CONNECTED`")
         );
 
+        let mut line: Cursor<&[u8]> = Cursor::new(b"CONNECTED <sonic-server v1.10.2>\r\n");
+
+        assert_eq!(
+            Recv::Connected(ServerInfo {
+                version: Version::new(1, 10, 2)
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `CONNECTED`")
+        );
+
+        assert!(Version::new(1, 10, 2) > Version::new(1, 9, 0));
+
         let mut line: Cursor<&[u8]> = Cursor::new(b"STARTED search protocol(1) buffer(20000)\r\n");
 
         assert_eq!(
-            Recv::Started(Some(Mode::Search), 20000),
+            Recv::Started(SessionInfo {
+                mode: Some(Mode::Search),
+                protocol: 1,
+                buffer_size: 20000
+            }),
             Recv::parse(&mut line).expect("Failed to parse; `STARTED`")
         );
 
@@ -189,6 +559,10 @@ mod test {
             Recv::parse(&mut line).expect("Failed to parse; `PENDING`")
         );
 
+        let mut line: Cursor<&[u8]> = Cursor::new(b"PENDING Bt2m\xff2gYa\r\n");
+
+        assert!(Recv::parse(&mut line).is_err());
+
         let mut line: Cursor<&[u8]> = Cursor::new(b"ENDED quit\r\n");
 
         assert_eq!(
@@ -202,10 +576,7 @@ mod test {
         assert_eq!(
             Recv::EventQuery(
                 "Bt2m2gYa".into(),
-                vec![
-                    "conversation:71f3d63b".into(),
-                    "conversation:6501e83a".into()
-                ]
+                Words::from(vec!["conversation:71f3d63b", "conversation:6501e83a"])
             ),
             Recv::parse(&mut line).expect("Failed to parse; `EVENT QUEUE`")
         );
@@ -213,18 +584,300 @@ mod test {
         let mut line: Cursor<&[u8]> = Cursor::new(b"EVENT SUGGEST z98uDE0f valerian valala\r\n");
 
         assert_eq!(
-            Recv::EventSuggest("z98uDE0f".into(), vec!["valerian".into(), "valala".into()]),
+            Recv::EventSuggest("z98uDE0f".into(), Words::from(vec!["valerian", "valala"])),
             Recv::parse(&mut line).expect("Failed to parse; `EVENT SUGGEST`")
         );
 
+        let mut line: Cursor<&[u8]> = Cursor::new(b"EVENT LIST u3j8Ks2a hello valerian\r\n");
+
+        assert_eq!(
+            Recv::EventList("u3j8Ks2a".into(), Words::from(vec!["hello", "valerian"])),
+            Recv::parse(&mut line).expect("Failed to parse; `EVENT LIST`")
+        );
+
         let mut line: Cursor<&[u8]> =
             Cursor::new(b"ERR invalid_format(PUSH <collection> <bucket> <object> \"<text>\")\r\n");
 
         assert_eq!(
-            Recv::Err(
-                " invalid_format(PUSH <collection> <bucket> <object> \"<text>\")".to_string()
-            ),
+            Recv::Err(ServerError {
+                kind: ErrorKind::InvalidFormat,
+                detail: Some("PUSH <collection> <bucket> <object> \"<text>\"".to_string()),
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `ERR`")
+        );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"ERR buffer_overflow\r\n");
+
+        assert_eq!(
+            Recv::Err(ServerError {
+                kind: ErrorKind::BufferOverflow,
+                detail: None,
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `ERR`")
+        );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"ERR something_new(details)\r\n");
+
+        assert_eq!(
+            Recv::Err(ServerError {
+                kind: ErrorKind::Unknown("something_new".to_string()),
+                detail: Some("details".to_string()),
+            }),
             Recv::parse(&mut line).expect("Failed to parse; `ERR`")
         );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"STARTED control protocol(1) buffer(20000)\r\n");
+
+        assert_eq!(
+            Recv::Started(SessionInfo {
+                mode: Some(Mode::Control),
+                protocol: 1,
+                buffer_size: 20000
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `STARTED control`")
+        );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"RESULT 2\r\n");
+
+        assert_eq!(
+            Recv::Result(2),
+            Recv::parse(&mut line).expect("Failed to parse; `RESULT` count")
+        );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"RESULT manuals(commands)\r\n");
+
+        assert_eq!(
+            Recv::ResultPairs(vec![("manuals".to_string(), "commands".to_string())]),
+            Recv::parse(&mut line).expect("Failed to parse; `RESULT` pairs")
+        );
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"RESULT commands(QUERY, SUGGEST, PING)\r\n");
+
+        let capabilities = match Recv::parse(&mut line) {
+            Ok(Recv::Commands(capabilities)) => capabilities,
+            frame => panic!("Failed to parse; `RESULT` commands {:?}", frame),
+        };
+
+        assert!(capabilities.contains("QUERY"));
+        assert!(capabilities.contains("PING"));
+        assert!(!capabilities.contains("LIST"));
+
+        let mut capabilities = Capabilities {
+            commands: vec!["TRIGGER".to_string()].into_iter().collect(),
+            ..Capabilities::default()
+        };
+
+        assert!(capabilities.contains_action("TRIGGER", "backup"));
+        assert!(!capabilities.contains_action("LIST", "backup"));
+
+        capabilities.set_actions("TRIGGER", "consolidate, restore");
+
+        assert!(capabilities.contains_action("TRIGGER", "consolidate"));
+        assert!(!capabilities.contains_action("TRIGGER", "backup"));
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"RESULT\r\n");
+
+        assert!(Recv::parse(&mut line).is_err());
+
+        let mut line: Cursor<&[u8]> = Cursor::new(
+            b"RESULT uptime(4) clients_connected(1) commands_total(2) command_latency_best(1) command_latency_worst(3) kv_open_count(0) fst_open_count(0) fst_consolidate_count(0)\r\n",
+        );
+
+        assert_eq!(
+            Recv::Info(Info {
+                uptime: 4,
+                clients_connected: 1,
+                commands_total: 2,
+                command_latency_best: 1,
+                command_latency_worst: 3,
+                kv_open_count: 0,
+                fst_open_count: 0,
+                fst_consolidate_count: 0,
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `RESULT` info")
+        );
+
+        // Without `uptime`, with statistics this client doesn't know.
+        let mut line: Cursor<&[u8]> = Cursor::new(
+            b"RESULT clients_connected(1) kv_open_count(2) build(v1.3.0-beta, x86_64)\r\n",
+        );
+
+        assert_eq!(
+            Recv::Info(Info {
+                clients_connected: 1,
+                kv_open_count: 2,
+                ..Info::default()
+            }),
+            Recv::parse(&mut line).expect("Failed to parse; `RESULT` info")
+        );
+    }
+
+    #[test]
+    fn frame_parse_unknown() {
+        for raw in &["Bt2m2gYa", "EVENT SOMETHING Bt2m2gYa", ""] {
+            let frame = format!("{}\r\n", raw);
+
+            let mut line: Cursor<&[u8]> = Cursor::new(frame.as_bytes());
+            let err = Recv::parse(&mut line).expect_err("Failed to reject; unknown frame");
+            assert!(err.to_string().contains(raw));
+
+            let mut line: Cursor<&[u8]> = Cursor::new(frame.as_bytes());
+            assert_eq!(
+                Recv::Unknown(raw.to_string()),
+                Recv::parse_with(&mut line, Parsing::Lenient)
+                    .expect("Failed to parse; unknown frame")
+            );
+        }
+
+        let mut line: Cursor<&[u8]> = Cursor::new(b"PONG\r\n");
+
+        assert_eq!(
+            Recv::Pong,
+            Recv::parse_with(&mut line, Parsing::Lenient).expect("Failed to parse; `PONG`")
+        );
+    }
+
+    #[test]
+    fn frame_recv_to_string() {
+        let frames = vec![
+            Recv::Connected(ServerInfo {
+                version: Version::new(1, 10, 2),
+            }),
+            Recv::Started(SessionInfo {
+                mode: Some(Mode::Ingest),
+                protocol: 1,
+                buffer_size: 20000,
+            }),
+            Recv::Pending("Bt2m2gYa".into()),
+            Recv::Ok,
+            Recv::Pong,
+            Recv::EventQuery(
+                "Bt2m2gYa".into(),
+                Words::from(vec!["conversation:71f3d63b", "conversation:6501e83a"]),
+            ),
+            Recv::EventSuggest("z98uDE0f".into(), Words::from(vec!["valerian"])),
+            Recv::EventList("u3j8Ks2a".into(), Words::default()),
+            Recv::Result(42),
+            Recv::Commands(Capabilities {
+                commands: vec!["PING".to_string(), "QUERY".to_string()]
+                    .into_iter()
+                    .collect(),
+                ..Capabilities::default()
+            }),
+            Recv::ResultPairs(vec![("manuals".into(), "commands".into())]),
+            Recv::Info(Info {
+                uptime: 4,
+                commands_total: 2,
+                ..Info::default()
+            }),
+            Recv::Ended("quit".into()),
+            Recv::Err(ServerError {
+                kind: ErrorKind::InvalidFormat,
+                detail: Some("PUSH <collection> <bucket> <object> \"<text>\"".into()),
+            }),
+            Recv::Err(ServerError {
+                kind: ErrorKind::UnknownCommand,
+                detail: None,
+            }),
+        ];
+
+        for frame in frames {
+            let line = frame.to_string();
+            let mut src: Cursor<&[u8]> = Cursor::new(line.as_bytes());
+
+            assert_eq!(
+                frame,
+                Recv::parse(&mut src).expect("Failed to parse; round trip")
+            );
+            assert_eq!(line.len() as u64, src.position());
+        }
+
+        assert_eq!(
+            "STARTED search protocol(1) buffer(20000)\r\n".to_string(),
+            Recv::Started(SessionInfo {
+                mode: Some(Mode::Search),
+                protocol: 1,
+                buffer_size: 20000
+            })
+            .to_string()
+        );
+
+        assert_eq!(
+            "ERR unknown_command\r\n".to_string(),
+            Recv::Err(ServerError {
+                kind: ErrorKind::UnknownCommand,
+                detail: None
+            })
+            .to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn frame_recv_serde() {
+        let frames = vec![
+            (r#""ok""#, Recv::Ok),
+            (
+                r#"{"pending":"Bt2m2gYa"}"#,
+                Recv::Pending("Bt2m2gYa".into()),
+            ),
+            (
+                r#"{"event_query":["Bt2m2gYa",["conversation:71f3d63b","conversation:6501e83a"]]}"#,
+                Recv::EventQuery(
+                    "Bt2m2gYa".into(),
+                    Words::from(vec!["conversation:71f3d63b", "conversation:6501e83a"]),
+                ),
+            ),
+            (r#"{"result":3}"#, Recv::Result(3)),
+            (
+                r#"{"err":{"kind":"query_limit","detail":null}}"#,
+                Recv::Err(ServerError {
+                    kind: ErrorKind::QueryLimit,
+                    detail: None,
+                }),
+            ),
+            (
+                r#"{"err":{"kind":{"unknown":"something_new"},"detail":"details"}}"#,
+                Recv::Err(ServerError {
+                    kind: ErrorKind::Unknown("something_new".into()),
+                    detail: Some("details".into()),
+                }),
+            ),
+            (
+                r#"{"connected":{"version":{"major":1,"minor":10,"patch":2}}}"#,
+                Recv::Connected(ServerInfo {
+                    version: Version::new(1, 10, 2),
+                }),
+            ),
+            (
+                r#"{"started":{"mode":"ingest","protocol":1,"buffer_size":20000}}"#,
+                Recv::Started(SessionInfo {
+                    mode: Some(Mode::Ingest),
+                    protocol: 1,
+                    buffer_size: 20000,
+                }),
+            ),
+        ];
+
+        for (json, frame) in frames {
+            assert_eq!(
+                json,
+                serde_json::to_string(&frame).expect("Failed to serialize")
+            );
+            assert_eq!(
+                frame,
+                serde_json::from_str(json).expect("Failed to deserialize")
+            );
+        }
+
+        // Read back as two words.
+        assert!(serde_json::from_str::<Words>(r#"["conversation:71f3d63b valerian"]"#).is_err());
+        assert!(serde_json::from_str::<Words>(r#"[""]"#).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_words_space() {
+        let _words = Words::from(vec!["conversation:71f3d63b valerian"]);
     }
 }
@@ -1,23 +1,41 @@
-use crate::frame::Mode;
+use bytes::BytesMut;
+use std::fmt::{self, Write};
+use std::io::Cursor;
+
+use crate::frame::lang::Lang;
+use crate::frame::{get_line, key_values, Error, Mode};
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Send {
     Start(Mode, String),
     Query(Query),
     Push(Push),
+    Pop(Pop),
     Ping,
     Suggest(Suggest),
+    List(List),
     Count(Count),
+    FlushCollection(FlushCollection),
+    FlushBucket(FlushBucket),
+    FlushObject(FlushObject),
+    Trigger(Trigger),
+    Info,
+    // `HELP [<manual>]`, e.g. `HELP commands`.
+    Help(Option<String>),
     Quit,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
     collection: String,
     bucket: String,
     terms: String,
     limit: Option<u64>,
     offset: Option<u64>,
+    lang: Option<Lang>,
 }
 
 impl Query {
@@ -28,6 +46,7 @@ impl Query {
             terms,
             limit: None,
             offset: None,
+            lang: None,
         }
     }
 
@@ -40,28 +59,48 @@ impl Query {
         self.offset = Some(offset);
         self
     }
+
+    pub fn lang(mut self, lang: Lang) -> Self {
+        self.lang = Some(lang);
+        self
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)
+    }
 }
 
-impl ToString for Query {
-    fn to_string(&self) -> String {
-        let mut s = format!("{} {} \"{}\"", self.collection, self.bucket, self.terms);
+impl fmt::Display for Query {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} {} {}",
+            self.collection,
+            self.bucket,
+            Quoted(&self.terms)
+        )?;
         if let Some(limit) = self.limit {
-            s.push_str(&format!(" {}", limit));
+            write!(fmt, " LIMIT({})", limit)?;
         };
         if let Some(offset) = self.offset {
-            s.push_str(&format!(" {}", offset));
+            write!(fmt, " OFFSET({})", offset)?;
         };
-        s
+        if let Some(lang) = &self.lang {
+            write!(fmt, " LANG({})", lang)?;
+        };
+        Ok(())
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Push {
     collection: String,
     bucket: String,
     object: String,
     text: String,
-    lang: Option<String>,
+    lang: Option<Lang>,
 }
 
 impl Push {
@@ -75,25 +114,192 @@ impl Push {
         }
     }
 
-    pub fn lang(&mut self, lang: String) {
+    pub fn lang(mut self, lang: Lang) -> Self {
         self.lang = Some(lang);
+        self
+    }
+
+    /// Split into as many `Push` as needed for every `PUSH` line to fit in
+    /// `max_len` bytes (the `buffer` size of `STARTED`), cutting `text`
+    /// between words whenever possible, and never inside a character.
+    pub fn split(&self, max_len: usize) -> Vec<Push> {
+        let part = |text: &str| Push {
+            collection: self.collection.clone(),
+            bucket: self.bucket.clone(),
+            object: self.object.clone(),
+            text: text.to_string(),
+            lang: self.lang,
+        };
+
+        let overhead = Send::Push(part("")).to_string().len();
+        let budget = max_len.saturating_sub(overhead);
+
+        if self.text.chars().map(escaped_len).sum::<usize>() <= budget {
+            return vec![self.clone()];
+        }
+
+        let mut texts = Vec::new();
+        // Current part is `self.text[start..]`, `len` bytes once escaped.
+        let mut start = 0;
+        let mut len = 0;
+        // Last whitespace seen in the current part, and where it ends.
+        let mut cut = None;
+
+        for (i, c) in self.text.char_indices() {
+            let width = escaped_len(c);
+
+            while len + width > budget && i > start {
+                let (end, next) = match cut {
+                    Some((cut, next)) if cut > start => (cut, next),
+                    _ => (i, i),
+                };
+                texts.push(&self.text[start..end]);
+
+                start = next;
+                len = self.text[start..i].chars().map(escaped_len).sum();
+                cut = None;
+            }
+
+            if c.is_whitespace() {
+                cut = Some((i, i + c.len_utf8()));
+            }
+            len += width;
+        }
+        texts.push(&self.text[start..]);
+
+        texts
+            .into_iter()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(part)
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)?;
+        check_name("object", &self.object)
     }
 }
 
-impl ToString for Push {
-    fn to_string(&self) -> String {
-        let mut s = format!(
-            "{} {} {} \"{}\"",
-            self.collection, self.bucket, self.object, self.text
-        );
+impl fmt::Display for Push {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} {} {} {}",
+            self.collection,
+            self.bucket,
+            self.object,
+            Quoted(&self.text)
+        )?;
         if let Some(lang) = &self.lang {
-            s.push_str(&format!(" {}", lang));
+            write!(fmt, " LANG({})", lang)?;
         };
-        s
+        Ok(())
+    }
+}
+
+/// Enumerate the words indexed in a bucket, on `search` mode.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct List {
+    collection: String,
+    bucket: String,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl List {
+    pub fn new(collection: String, bucket: String) -> Self {
+        List {
+            collection,
+            bucket,
+            limit: None,
+            offset: None,
+        }
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// The same listing, one page (`limit` words) further.
+    /// `None` if no `limit` was set, the page size is then up to the server.
+    ///
+    /// The last page is the one answered with less than `limit` words.
+    pub fn next_page(&self) -> Option<Self> {
+        let limit = self.limit?;
+        let offset = self.offset.unwrap_or(0) + limit;
+
+        Some(self.clone().offset(offset))
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} {}", self.collection, self.bucket)?;
+        if let Some(limit) = self.limit {
+            write!(fmt, " LIMIT({})", limit)?;
+        };
+        if let Some(offset) = self.offset {
+            write!(fmt, " OFFSET({})", offset)?;
+        };
+        Ok(())
     }
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pop {
+    collection: String,
+    bucket: String,
+    object: String,
+    text: String,
+}
+
+impl Pop {
+    pub fn new(collection: String, bucket: String, object: String, text: String) -> Self {
+        Pop {
+            collection,
+            bucket,
+            object,
+            text,
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)?;
+        check_name("object", &self.object)
+    }
+}
+
+impl fmt::Display for Pop {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} {} {} {}",
+            self.collection,
+            self.bucket,
+            self.object,
+            Quoted(&self.text)
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Count {
     collection: String,
     bucket: Option<String>,
@@ -115,27 +321,115 @@ impl Count {
     }
 
     pub fn object(mut self, object: String) -> Self {
-        if let Some(_) = self.bucket {
+        if self.bucket.is_some() {
             self.object = Some(object);
         }
         self
     }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        if let Some(bucket) = &self.bucket {
+            check_name("bucket", bucket)?;
+        };
+        if let Some(object) = &self.object {
+            check_name("object", object)?;
+        };
+        Ok(())
+    }
 }
 
-impl ToString for Count {
-    fn to_string(&self) -> String {
-        let mut s = format!("{}", self.collection);
+impl fmt::Display for Count {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.collection)?;
         if let Some(bucket) = &self.bucket {
-            s.push_str(&format!(" {}", bucket));
+            write!(fmt, " {}", bucket)?;
         };
         if let Some(object) = &self.object {
-            s.push_str(&format!(" {}", object));
+            write!(fmt, " {}", object)?;
         };
-        s
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlushCollection {
+    collection: String,
+}
+
+impl FlushCollection {
+    pub fn new(collection: String) -> Self {
+        FlushCollection { collection }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)
+    }
+}
+
+impl fmt::Display for FlushCollection {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.collection)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlushBucket {
+    collection: String,
+    bucket: String,
+}
+
+impl FlushBucket {
+    pub fn new(collection: String, bucket: String) -> Self {
+        FlushBucket { collection, bucket }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)
+    }
+}
+
+impl fmt::Display for FlushBucket {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} {}", self.collection, self.bucket)
     }
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlushObject {
+    collection: String,
+    bucket: String,
+    object: String,
+}
+
+impl FlushObject {
+    pub fn new(collection: String, bucket: String, object: String) -> Self {
+        FlushObject {
+            collection,
+            bucket,
+            object,
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)?;
+        check_name("object", &self.object)
+    }
+}
+
+impl fmt::Display for FlushObject {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} {} {}", self.collection, self.bucket, self.object)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Suggest {
     collection: String,
     bucket: String,
@@ -153,34 +447,415 @@ impl Suggest {
         }
     }
 
-    pub fn limit(&mut self, limit: u64) {
+    pub fn limit(mut self, limit: u64) -> Self {
         self.limit = Some(limit);
+        self
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        check_name("collection", &self.collection)?;
+        check_name("bucket", &self.bucket)
     }
 }
 
-impl ToString for Suggest {
-    fn to_string(&self) -> String {
-        let mut s = format!("{} {} \"{}\"", self.collection, self.bucket, self.word);
+impl fmt::Display for Suggest {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} {} {}",
+            self.collection,
+            self.bucket,
+            Quoted(&self.word)
+        )?;
         if let Some(limit) = &self.limit {
-            s.push_str(&format!(" {}", limit));
+            write!(fmt, " LIMIT({})", limit)?;
         };
-        s
+        Ok(())
+    }
+}
+
+/// Actions accepted by `TRIGGER`, on `control` mode.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Trigger {
+    Consolidate,
+    Backup(String),
+    Restore(String),
+}
+
+impl Trigger {
+    /// The action name, as listed by `TRIGGER` alone.
+    pub fn action(&self) -> &'static str {
+        match self {
+            Trigger::Consolidate => "consolidate",
+            Trigger::Backup(_path) => "backup",
+            Trigger::Restore(_path) => "restore",
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Trigger::Consolidate => Ok(()),
+            Trigger::Backup(path) | Trigger::Restore(path) => check_name("path", path),
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::Consolidate => fmt.write_str(self.action()),
+            Trigger::Backup(path) | Trigger::Restore(path) => {
+                write!(fmt, "{} {}", self.action(), path)
+            }
+        }
     }
 }
-impl ToString for Send {
-    fn to_string(&self) -> String {
+
+impl Send {
+    /// Check that every unquoted argument is a single, non empty, word.
+    /// Otherwise the frame would be misread by the server, or worse,
+    /// carry a second command.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
         match self {
-            Send::Start(mode, passwd) => format!("START {} {}\r\n", mode.to_string(), passwd),
-            Send::Quit => format!("QUIT\r\n"),
-            Send::Query(query) => format!("QUERY {}\r\n", query.to_string()),
-            Send::Push(push) => format!("PUSH {}\r\n", push.to_string()),
-            Send::Count(count) => format!("COUNT {}\r\n", count.to_string()),
-            Send::Suggest(suggest) => format!("SUGGEST {}\r\n", suggest.to_string()),
-            Send::Ping => format!("PING\r\n"),
+            Send::Start(_mode, passwd) => check_password(passwd),
+            Send::Query(query) => query.validate(),
+            Send::Push(push) => push.validate(),
+            Send::Pop(pop) => pop.validate(),
+            Send::Count(count) => count.validate(),
+            Send::FlushCollection(flush) => flush.validate(),
+            Send::FlushBucket(flush) => flush.validate(),
+            Send::FlushObject(flush) => flush.validate(),
+            Send::Suggest(suggest) => suggest.validate(),
+            Send::List(list) => list.validate(),
+            Send::Trigger(trigger) => trigger.validate(),
+            Send::Help(Some(manual)) => check_name("manual", manual),
+            Send::Help(None) | Send::Ping | Send::Info | Send::Quit => Ok(()),
         }
     }
 }
 
+impl Send {
+    /// The command name, as listed by `HELP commands`.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Send::Start(..) => "START",
+            Send::Query(_) => "QUERY",
+            Send::Push(_) => "PUSH",
+            Send::Pop(_) => "POP",
+            Send::Ping => "PING",
+            Send::Suggest(_) => "SUGGEST",
+            Send::List(_) => "LIST",
+            Send::Count(_) => "COUNT",
+            Send::FlushCollection(_) => "FLUSHC",
+            Send::FlushBucket(_) => "FLUSHB",
+            Send::FlushObject(_) => "FLUSHO",
+            Send::Trigger(_) => "TRIGGER",
+            Send::Info => "INFO",
+            Send::Help(_) => "HELP",
+            Send::Quit => "QUIT",
+        }
+    }
+}
+
+impl Send {
+    /// Read a client line, as written by `encode`.
+    pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Self, Error> {
+        let line = get_line(src)?;
+        Send::parse_line(std::str::from_utf8(line)?)
+    }
+
+    /// Read a client `line`, without its `\r\n`.
+    pub(crate) fn parse_line(line: &str) -> Result<Self, Error> {
+        let mut args = Args::new(line);
+
+        let frame = match args.word("command")? {
+            "START" => {
+                let mode = args.word("mode")?.parse()?;
+                Send::Start(mode, args.word("password")?.to_string())
+            }
+            "QUERY" => {
+                let mut query = Query::new(
+                    args.word("collection")?.to_string(),
+                    args.word("bucket")?.to_string(),
+                    args.quoted("terms")?,
+                );
+                for (key, value) in args.modifiers()? {
+                    query = match key {
+                        "LIMIT" => query.limit(value.parse()?),
+                        "OFFSET" => query.offset(value.parse()?),
+                        "LANG" => query.lang(value.parse()?),
+                        _ => return Err(format!("invalid frame; `QUERY` {}", key).into()),
+                    };
+                }
+                Send::Query(query)
+            }
+            "PUSH" => {
+                let mut push = Push::new(
+                    args.word("collection")?.to_string(),
+                    args.word("bucket")?.to_string(),
+                    args.word("object")?.to_string(),
+                    args.quoted("text")?,
+                );
+                for (key, value) in args.modifiers()? {
+                    push = match key {
+                        "LANG" => push.lang(value.parse()?),
+                        _ => return Err(format!("invalid frame; `PUSH` {}", key).into()),
+                    };
+                }
+                Send::Push(push)
+            }
+            "POP" => Send::Pop(Pop::new(
+                args.word("collection")?.to_string(),
+                args.word("bucket")?.to_string(),
+                args.word("object")?.to_string(),
+                args.quoted("text")?,
+            )),
+            "PING" => Send::Ping,
+            "SUGGEST" => {
+                let mut suggest = Suggest::new(
+                    args.word("collection")?.to_string(),
+                    args.word("bucket")?.to_string(),
+                    args.quoted("word")?,
+                );
+                for (key, value) in args.modifiers()? {
+                    suggest = match key {
+                        "LIMIT" => suggest.limit(value.parse()?),
+                        _ => return Err(format!("invalid frame; `SUGGEST` {}", key).into()),
+                    };
+                }
+                Send::Suggest(suggest)
+            }
+            "LIST" => {
+                let mut list = List::new(
+                    args.word("collection")?.to_string(),
+                    args.word("bucket")?.to_string(),
+                );
+                for (key, value) in args.modifiers()? {
+                    list = match key {
+                        "LIMIT" => list.limit(value.parse()?),
+                        "OFFSET" => list.offset(value.parse()?),
+                        _ => return Err(format!("invalid frame; `LIST` {}", key).into()),
+                    };
+                }
+                Send::List(list)
+            }
+            "COUNT" => {
+                let mut count = Count::new(args.word("collection")?.to_string());
+                if let Some(bucket) = args.next_word() {
+                    count = count.bucket(bucket.to_string());
+                }
+                if let Some(object) = args.next_word() {
+                    count = count.object(object.to_string());
+                }
+                Send::Count(count)
+            }
+            "FLUSHC" => {
+                Send::FlushCollection(FlushCollection::new(args.word("collection")?.to_string()))
+            }
+            "FLUSHB" => Send::FlushBucket(FlushBucket::new(
+                args.word("collection")?.to_string(),
+                args.word("bucket")?.to_string(),
+            )),
+            "FLUSHO" => Send::FlushObject(FlushObject::new(
+                args.word("collection")?.to_string(),
+                args.word("bucket")?.to_string(),
+                args.word("object")?.to_string(),
+            )),
+            "TRIGGER" => Send::Trigger(match args.word("action")? {
+                "consolidate" => Trigger::Consolidate,
+                "backup" => Trigger::Backup(args.word("path")?.to_string()),
+                "restore" => Trigger::Restore(args.word("path")?.to_string()),
+                action => return Err(format!("invalid frame; `TRIGGER` {}", action).into()),
+            }),
+            "INFO" => Send::Info,
+            "HELP" => Send::Help(args.next_word().map(str::to_string)),
+            "QUIT" => Send::Quit,
+            command => return Err(format!("invalid frame; unknown command `{}`", command).into()),
+        };
+
+        args.end()?;
+
+        Ok(frame)
+    }
+}
+
+impl Send {
+    /// Write the line, `\r\n` included, at the end of `dst`.
+    ///
+    /// Nothing but `dst` is allocated, so the same buffer can be reused
+    /// for every frame, as `ClientCodec` does.
+    pub fn encode(&self, dst: &mut BytesMut) {
+        // Writing into a `BytesMut` never fails, it grows as needed.
+        let _ = write!(dst, "{}", self);
+    }
+}
+
+/// The line, `\r\n` included.
+impl fmt::Display for Send {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Send::Start(mode, passwd) => write!(fmt, "START {} {}\r\n", mode, passwd),
+            Send::Quit => fmt.write_str("QUIT\r\n"),
+            Send::Query(query) => write!(fmt, "QUERY {}\r\n", query),
+            Send::Push(push) => write!(fmt, "PUSH {}\r\n", push),
+            Send::Pop(pop) => write!(fmt, "POP {}\r\n", pop),
+            Send::Count(count) => write!(fmt, "COUNT {}\r\n", count),
+            Send::FlushCollection(flush) => write!(fmt, "FLUSHC {}\r\n", flush),
+            Send::FlushBucket(flush) => write!(fmt, "FLUSHB {}\r\n", flush),
+            Send::FlushObject(flush) => write!(fmt, "FLUSHO {}\r\n", flush),
+            Send::Suggest(suggest) => write!(fmt, "SUGGEST {}\r\n", suggest),
+            Send::List(list) => write!(fmt, "LIST {}\r\n", list),
+            Send::Ping => fmt.write_str("PING\r\n"),
+            Send::Trigger(trigger) => write!(fmt, "TRIGGER {}\r\n", trigger),
+            Send::Info => fmt.write_str("INFO\r\n"),
+            Send::Help(Some(manual)) => write!(fmt, "HELP {}\r\n", manual),
+            Send::Help(None) => fmt.write_str("HELP\r\n"),
+        }
+    }
+}
+
+/// `text` between `"`, escaping the characters that would end it early
+/// (`"` and `\\`) or end the frame (`\r` and `\n`).
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_char('"')?;
+        // Unescaped runs are written at once.
+        let mut start = 0;
+        for (i, c) in self.0.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\r' => "\\r",
+                '\n' => "\\n",
+                _ => continue,
+            };
+            fmt.write_str(&self.0[start..i])?;
+            fmt.write_str(escaped)?;
+            start = i + 1;
+        }
+        fmt.write_str(&self.0[start..])?;
+        fmt.write_char('"')
+    }
+}
+
+/// Reads the arguments of a client line, in order.
+struct Args<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Args<'a> {
+    fn new(line: &'a str) -> Self {
+        Args {
+            rest: line.trim_start(),
+        }
+    }
+
+    fn next_word(&mut self) -> Option<&'a str> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let end = self.rest.find(' ').unwrap_or(self.rest.len());
+        let word = &self.rest[..end];
+        self.rest = self.rest[end..].trim_start();
+
+        Some(word)
+    }
+
+    fn word(&mut self, kind: &str) -> Result<&'a str, Error> {
+        self.next_word()
+            .ok_or_else(|| format!("invalid frame; missing {}", kind).into())
+    }
+
+    /// Undo `Quoted`.
+    fn quoted(&mut self, kind: &str) -> Result<String, Error> {
+        let mut chars = self.rest.char_indices();
+
+        if chars.next() != Some((0, '"')) {
+            return Err(format!("invalid frame; unquoted {}", kind).into());
+        }
+
+        let mut text = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = self.rest[i + 1..].trim_start();
+                    return Ok(text);
+                }
+                '\\' => match chars.next() {
+                    Some((_, '"')) => text.push('"'),
+                    Some((_, '\\')) => text.push('\\'),
+                    Some((_, 'r')) => text.push('\r'),
+                    Some((_, 'n')) => text.push('\n'),
+                    _ => return Err(format!("invalid frame; escape in {}", kind).into()),
+                },
+                c => text.push(c),
+            }
+        }
+
+        Err(format!("invalid frame; unterminated {}", kind).into())
+    }
+
+    /// The trailing `LIMIT(10) OFFSET(5) ...`, if any.
+    fn modifiers(&mut self) -> Result<Vec<(&'a str, &'a str)>, Error> {
+        if self.rest.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let modifiers = key_values(self.rest)?;
+        self.rest = "";
+
+        Ok(modifiers)
+    }
+
+    fn end(&self) -> Result<(), Error> {
+        if !self.rest.is_empty() {
+            return Err(format!("invalid frame; unexpected `{}`", self.rest).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Length of `c` once escaped by `Quoted`.
+fn escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' | '\r' | '\n' => 2,
+        c => c.len_utf8(),
+    }
+}
+
+/// Collection, bucket and object names are sent unquoted, so they must be a single word.
+fn check_name(kind: &str, name: &str) -> Result<(), Error> {
+    if name.is_empty() {
+        return Err(format!("invalid frame; empty {}", kind).into());
+    }
+
+    if name.contains(|c: char| c.is_whitespace() || c == '"') {
+        return Err(format!("invalid frame; {} `{}` is not a single word", kind, name).into());
+    }
+
+    Ok(())
+}
+
+/// As `check_name`, but the password is left out of the error, which may be logged.
+fn check_password(passwd: &str) -> Result<(), Error> {
+    if passwd.is_empty() {
+        return Err("invalid frame; empty password".into());
+    }
+
+    if passwd.contains(|c: char| c.is_whitespace() || c == '"') {
+        return Err("invalid frame; password is not a single word".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -203,6 +878,36 @@ mod test {
             .to_string()
         );
 
+        assert_eq!(
+            "QUERY messages user:0dcde3a6 \"valerian saliou\" LIMIT(10) OFFSET(5) LANG(eng)\r\n"
+                .to_string(),
+            Send::Query(
+                Query::new(
+                    "messages".into(),
+                    "user:0dcde3a6".into(),
+                    "valerian saliou".into()
+                )
+                .limit(10)
+                .offset(5)
+                .lang(Lang::Eng)
+            )
+            .to_string()
+        );
+
+        let push = Push::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "conversation:71f3d63b".into(),
+            "Bonjour Valerian".into(),
+        )
+        .lang(Lang::Fra);
+
+        assert_eq!(
+            "PUSH messages user:0dcde3a6 conversation:71f3d63b \"Bonjour Valerian\" LANG(fra)\r\n"
+                .to_string(),
+            Send::Push(push).to_string()
+        );
+
         assert_eq!(
             "PUSH messages user:0dcde3a6 conversation:71f3d63b \"Hello Valerian Saliou, how are you today?\"\r\n".to_string(),
             Send::Push(Push::new(
@@ -214,6 +919,83 @@ mod test {
             .to_string()
         );
 
+        assert_eq!(
+            "POP messages user:0dcde3a6 conversation:71f3d63b \"Hello \\\"Valerian\\\"\"\r\n"
+                .to_string(),
+            Send::Pop(Pop::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+                "Hello \"Valerian\"".into()
+            ))
+            .to_string()
+        );
+
+        assert_eq!(
+            "PUSH messages user:0dcde3a6 conversation:71f3d63b \"a\\\\b\\r\\nQUIT\"\r\n"
+                .to_string(),
+            Send::Push(Push::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+                "a\\b\r\nQUIT".into()
+            ))
+            .to_string()
+        );
+
+        assert!(Send::Push(Push::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "conversation:71f3d63b".into(),
+            "Hello".into()
+        ))
+        .validate()
+        .is_ok());
+
+        // `PUSH c b o ""\r\n` leaves 10 bytes of text on 25 bytes lines.
+        let push = Push::new(
+            "c".into(),
+            "b".into(),
+            "o".into(),
+            "Hello Valerian, ça va? Supercalifragilistic".into(),
+        );
+
+        assert_eq!(vec![push.clone()], push.split(100));
+
+        assert_eq!(
+            vec![
+                "PUSH c b o \"Hello\"\r\n".to_string(),
+                "PUSH c b o \"Valerian,\"\r\n".to_string(),
+                "PUSH c b o \"ça va?\"\r\n".to_string(),
+                "PUSH c b o \"Supercalif\"\r\n".to_string(),
+                "PUSH c b o \"ragilistic\"\r\n".to_string(),
+            ],
+            push.split(25)
+                .into_iter()
+                .map(|push| Send::Push(push).to_string())
+                .collect::<Vec<String>>()
+        );
+
+        assert!(Send::Push(Push::new(
+            "messages".into(),
+            "user:0dcde3a6\r\nFLUSHC messages".into(),
+            "conversation:71f3d63b".into(),
+            "Hello".into()
+        ))
+        .validate()
+        .is_err());
+
+        assert!(Send::Count(Count::new("".into())).validate().is_err());
+
+        let err = Send::Start(Mode::Search, "Secret Password".into())
+            .validate()
+            .expect_err("Failed to reject password");
+        assert_eq!(
+            "invalid frame; password is not a single word",
+            err.to_string()
+        );
+        assert!(!err.to_string().contains("Secret"));
+
         assert_eq!(
             "COUNT messages user:0dcde3a6 conversation:71f3d63b\r\n".to_string(),
             Send::Count(
@@ -224,6 +1006,27 @@ mod test {
             .to_string()
         );
 
+        assert_eq!(
+            "FLUSHC messages\r\n".to_string(),
+            Send::FlushCollection(FlushCollection::new("messages".into())).to_string()
+        );
+
+        assert_eq!(
+            "FLUSHB messages user:0dcde3a6\r\n".to_string(),
+            Send::FlushBucket(FlushBucket::new("messages".into(), "user:0dcde3a6".into()))
+                .to_string()
+        );
+
+        assert_eq!(
+            "FLUSHO messages user:0dcde3a6 conversation:71f3d63b\r\n".to_string(),
+            Send::FlushObject(FlushObject::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into()
+            ))
+            .to_string()
+        );
+
         assert_eq!("PING\r\n".to_string(), Send::Ping.to_string());
 
         assert_eq!(
@@ -234,6 +1037,223 @@ mod test {
                 "val".into()
             ))
             .to_string()
+        );
+
+        let suggest =
+            Suggest::new("messages".into(), "user:0dcde3a6".into(), "val".into()).limit(3);
+
+        assert_eq!(
+            "SUGGEST messages user:0dcde3a6 \"val\" LIMIT(3)\r\n".to_string(),
+            Send::Suggest(suggest).to_string()
+        );
+
+        let list = List::new("messages".into(), "user:0dcde3a6".into()).limit(10);
+
+        assert_eq!(
+            "LIST messages user:0dcde3a6 LIMIT(10)\r\n".to_string(),
+            Send::List(list.clone()).to_string()
+        );
+
+        let list = list.next_page().expect("Failed to get `LIST` next page");
+
+        assert_eq!(
+            "LIST messages user:0dcde3a6 LIMIT(10) OFFSET(10)\r\n".to_string(),
+            Send::List(list.clone()).to_string()
+        );
+
+        assert_eq!(
+            "LIST messages user:0dcde3a6 LIMIT(10) OFFSET(20)\r\n".to_string(),
+            Send::List(list.next_page().expect("Failed to get `LIST` next page")).to_string()
+        );
+
+        assert_eq!(
+            None,
+            List::new("messages".into(), "user:0dcde3a6".into()).next_page()
+        );
+
+        assert_eq!(
+            "TRIGGER consolidate\r\n".to_string(),
+            Send::Trigger(Trigger::Consolidate).to_string()
+        );
+
+        assert_eq!(
+            "TRIGGER backup 2020-05-01\r\n".to_string(),
+            Send::Trigger(Trigger::Backup("2020-05-01".into())).to_string()
+        );
+
+        assert_eq!(
+            "TRIGGER restore 2020-05-01\r\n".to_string(),
+            Send::Trigger(Trigger::Restore("2020-05-01".into())).to_string()
+        );
+
+        assert_eq!("INFO\r\n".to_string(), Send::Info.to_string());
+
+        assert_eq!(
+            "HELP commands\r\n".to_string(),
+            Send::Help(Some("commands".into())).to_string()
+        );
+    }
+
+    #[test]
+    fn frame_send_encode() {
+        let frames = vec![
+            Send::Push(Push::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+                "Hello \"Valerian\"\r\n".into(),
+            )),
+            Send::Query(
+                Query::new("messages".into(), "user:0dcde3a6".into(), "valerian".into())
+                    .limit(10)
+                    .lang(Lang::Eng),
+            ),
+            Send::Ping,
+        ];
+
+        let mut dst = BytesMut::new();
+        for frame in &frames {
+            frame.encode(&mut dst);
+        }
+
+        assert_eq!(
+            frames
+                .iter()
+                .map(Send::to_string)
+                .collect::<String>()
+                .as_bytes(),
+            &dst[..]
+        );
+
+        // The buffer is reused once its content is written.
+        let capacity = dst.capacity();
+        dst.clear();
+        frames[0].encode(&mut dst);
+
+        assert_eq!(capacity, dst.capacity());
+        assert_eq!(frames[0].to_string().as_bytes(), &dst[..]);
+    }
+
+    #[test]
+    fn frame_send_parse() {
+        let push = Push::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "conversation:71f3d63b".into(),
+            "Hello \"Valerian\"\r\nC:\\ (done)".into(),
         )
+        .lang(Lang::Eng);
+
+        let suggest =
+            Suggest::new("messages".into(), "user:0dcde3a6".into(), "val".into()).limit(3);
+
+        let frames = vec![
+            Send::Start(Mode::Control, "SecretPassword".into()),
+            Send::Query(
+                Query::new(
+                    "messages".into(),
+                    "user:0dcde3a6".into(),
+                    "valerian saliou".into(),
+                )
+                .limit(10)
+                .offset(5)
+                .lang(Lang::Fra),
+            ),
+            Send::Query(Query::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "".into(),
+            )),
+            Send::Push(push),
+            Send::Pop(Pop::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+                "Hello".into(),
+            )),
+            Send::Ping,
+            Send::Suggest(suggest),
+            Send::List(List::new("messages".into(), "user:0dcde3a6".into()).offset(20)),
+            Send::Count(Count::new("messages".into()).bucket("user:0dcde3a6".into())),
+            Send::FlushCollection(FlushCollection::new("messages".into())),
+            Send::FlushBucket(FlushBucket::new("messages".into(), "user:0dcde3a6".into())),
+            Send::FlushObject(FlushObject::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+            )),
+            Send::Trigger(Trigger::Consolidate),
+            Send::Trigger(Trigger::Restore("2020-05-01".into())),
+            Send::Info,
+            Send::Help(Some("commands".into())),
+            Send::Help(None),
+            Send::Quit,
+        ];
+
+        for frame in frames {
+            let line = frame.to_string();
+            let mut src: Cursor<&[u8]> = Cursor::new(line.as_bytes());
+
+            assert_eq!(
+                frame,
+                Send::parse(&mut src).expect("Failed to parse; round trip")
+            );
+            assert_eq!(line.len() as u64, src.position());
+        }
+
+        for line in &[
+            "UNKNOWN\r\n",
+            "QUERY messages user:0dcde3a6 valerian\r\n",
+            "QUERY messages user:0dcde3a6 \"valerian\r\n",
+            "PUSH messages user:0dcde3a6 \"Hello\"\r\n",
+            "SUGGEST messages user:0dcde3a6 \"val\" OFFSET(1)\r\n",
+            "PING now\r\n",
+        ] {
+            let mut src: Cursor<&[u8]> = Cursor::new(line.as_bytes());
+            assert!(Send::parse(&mut src).is_err(), "{}", line);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn frame_send_serde() {
+        let frames = vec![
+            (r#""ping""#, Send::Ping),
+            (
+                r#"{"start":["search","SecretPassword"]}"#,
+                Send::Start(Mode::Search, "SecretPassword".into()),
+            ),
+            (
+                r#"{"query":{"collection":"messages","bucket":"user:0dcde3a6","terms":"valerian","limit":10,"offset":null,"lang":"eng"}}"#,
+                Send::Query(
+                    Query::new("messages".into(), "user:0dcde3a6".into(), "valerian".into())
+                        .limit(10)
+                        .lang(Lang::Eng),
+                ),
+            ),
+            (
+                r#"{"trigger":{"backup":"2020-05-01"}}"#,
+                Send::Trigger(Trigger::Backup("2020-05-01".into())),
+            ),
+            (r#"{"help":null}"#, Send::Help(None)),
+        ];
+
+        for (json, frame) in frames {
+            assert_eq!(
+                json,
+                serde_json::to_string(&frame).expect("Failed to serialize")
+            );
+            assert_eq!(
+                frame,
+                serde_json::from_str(json).expect("Failed to deserialize")
+            );
+        }
+
+        // Absent options may be left out.
+        assert_eq!(
+            Send::List(List::new("messages".into(), "user:0dcde3a6".into())),
+            serde_json::from_str(r#"{"list":{"collection":"messages","bucket":"user:0dcde3a6"}}"#)
+                .expect("Failed to deserialize; `LIST`")
+        );
     }
 }
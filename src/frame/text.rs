@@ -0,0 +1,167 @@
+use bytes::Bytes;
+use std::fmt;
+use std::ops::Deref;
+
+/// UTF-8 text sharing the read buffer of the frame it comes from, e.g. an id.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Text(Bytes);
+
+impl Text {
+    /// `text`, sliced out of `line`, which must hold it.
+    pub(crate) fn slice_of(line: &Bytes, text: &str) -> Self {
+        Text(line.slice_ref(text.as_bytes()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only ever built from a `str`, checked once when its line was read.
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&'static str> for Text {
+    fn from(src: &'static str) -> Text {
+        Text(Bytes::from_static(src.as_bytes()))
+    }
+}
+
+impl From<String> for Text {
+    fn from(src: String) -> Text {
+        Text(Bytes::from(src))
+    }
+}
+
+impl PartialEq<str> for Text {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// A string.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Text {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Text {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Text::from)
+    }
+}
+
+impl fmt::Debug for Text {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+/// Space separated words sharing the read buffer of the frame they come from,
+/// e.g. the keys of `EVENT QUERY`. Nothing is allocated until they are read.
+#[derive(Clone, Default)]
+pub struct Words(Bytes);
+
+impl Words {
+    /// `words`, sliced out of `line`, which must hold them.
+    pub(crate) fn slice_of(line: &Bytes, words: &str) -> Self {
+        Words(line.slice_ref(words.as_bytes()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .split(|b| *b == b' ')
+            .filter(|word| !word.is_empty())
+            // SAFETY: only ever built from a `str`, cut at ASCII spaces.
+            .map(|word| unsafe { std::str::from_utf8_unchecked(word) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    pub fn to_vec(&self) -> Vec<String> {
+        self.iter().map(str::to_string).collect()
+    }
+}
+
+/// Whether `word` is read back as is, once joined to others with spaces.
+fn is_word(word: &str) -> bool {
+    !word.is_empty() && !word.contains(' ')
+}
+
+impl<'a> From<Vec<&'a str>> for Words {
+    /// Panics if a word is empty or holds a space, as it would be read back
+    /// as other words.
+    fn from(src: Vec<&'a str>) -> Words {
+        assert!(
+            src.iter().all(|word| is_word(word)),
+            "invalid words; {:?}",
+            src
+        );
+
+        Words(Bytes::from(src.join(" ")))
+    }
+}
+
+impl PartialEq for Words {
+    fn eq(&self, other: &Words) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Words {}
+
+impl fmt::Debug for Words {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A list of strings.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Words {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Words {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let words = Vec::<String>::deserialize(deserializer)?;
+
+        if let Some(word) = words.iter().find(|word| !is_word(word)) {
+            return Err(serde::de::Error::custom(format!(
+                "invalid word `{}`; empty, or holding a space",
+                word
+            )));
+        }
+
+        Ok(Words(Bytes::from(words.join(" "))))
+    }
+}
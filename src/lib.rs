@@ -2,11 +2,12 @@
 //!
 //! This is an experimental project.
 
-#[macro_use]
-extern crate lazy_static;
-
 pub mod connection;
+#[cfg(test)]
+mod fake;
 pub mod frame;
+pub mod reconnect;
+pub mod session;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 
@@ -0,0 +1,406 @@
+//! Sessions surviving a restart of Sonic: `Reconnecting` dials again, with
+//! backoff, and starts a new session with the same mode and password.
+//!
+//! Commands are retried on the new session when it is safe to send them
+//! twice: `PING`, `QUERY`, `SUGGEST`, `LIST`, `COUNT` and `INFO`. Others,
+//! e.g. `PUSH`, go through `Reconnecting::session`, and fail as they would
+//! on a `Session`.
+//!
+//! ```no_run
+//! # async fn run() -> my_sonic_client::Result<()> {
+//! use my_sonic_client::frame::send::Query;
+//! use my_sonic_client::reconnect::{Backoff, Reconnecting};
+//! use my_sonic_client::session::Search;
+//! use std::time::Duration;
+//!
+//! let mut search = Reconnecting::new("[::1]:1491", Search, "SecretPassword".into())
+//!     .backoff(Backoff::default().attempts(5).max(Duration::from_secs(2)));
+//! let keys = search
+//!     .query(Query::new("messages".into(), "user:0dcde3a6".into(), "valerian".into()))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::connection::{Connection, TimedOut, Timeouts};
+use crate::frame::recv::Info;
+use crate::frame::send::{Count, List, Query, Suggest};
+use crate::frame::text::Words;
+use crate::session::{Control, HandshakeError, Ingest, Search, Session, SessionMode};
+use crate::Error;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::net::ToSocketAddrs;
+
+/// Delays between attempts to dial: doubling from `initial` up to `max`,
+/// each shortened by a random part of up to half, so that clients
+/// disconnected together don't all dial at once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            attempts: 8,
+        }
+    }
+}
+
+impl Backoff {
+    /// 100ms by default.
+    pub fn initial(mut self, initial: Duration) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// 10s by default.
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Attempts after the first one, 8 by default, before giving up
+    /// with the last error.
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// The delay before the attempt following `attempt`, counted from 0.
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = 1u32
+            .checked_shl(attempt)
+            .and_then(|factor| self.initial.checked_mul(factor))
+            .map_or(self.max, |delay| delay.min(self.max));
+
+        // `RandomState` is seeded anew each time, no need for a generator.
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(attempt);
+        let jitter = hasher.finish() % (delay.as_nanos() as u64 / 2 + 1);
+
+        delay - Duration::from_nanos(jitter)
+    }
+}
+
+/// A `Session` on `M`, started again whenever the connection is lost.
+///
+/// Nothing is dialed until the first command.
+#[derive(Debug)]
+pub struct Reconnecting<M, A> {
+    addr: A,
+    mode: M,
+    password: String,
+    timeouts: Timeouts,
+    backoff: Backoff,
+    session: Option<Session<M>>,
+}
+
+impl<M: SessionMode + Copy, A: ToSocketAddrs + Clone> Reconnecting<M, A> {
+    pub fn new(addr: A, mode: M, password: String) -> Self {
+        Reconnecting {
+            addr,
+            mode,
+            password,
+            timeouts: Timeouts::default(),
+            backoff: Backoff::default(),
+            session: None,
+        }
+    }
+
+    /// For each connection, as `Connection::connect_with`.
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// The current session, started anew if the connection was lost.
+    ///
+    /// Fails with the last error once every attempt failed, or at once
+    /// if the password or the mode is rejected.
+    pub async fn session(&mut self) -> Result<&mut Session<M>, Error> {
+        self.open(&mut 0).await
+    }
+
+    pub async fn ping(&mut self) -> Result<(), Error> {
+        self.retrying(|session| Box::pin(session.ping())).await
+    }
+
+    /// End the session, if one is open, with `QUIT`.
+    pub async fn quit(self) -> Result<(), Error> {
+        match self.session {
+            Some(session) if !session.is_closed() => session.quit().await.map(drop),
+            _ => Ok(()),
+        }
+    }
+
+    /// The current session, or a new one, dialing again on failure while
+    /// `attempt` is below the attempts.
+    async fn open(&mut self, attempt: &mut u32) -> Result<&mut Session<M>, Error> {
+        loop {
+            if let Some(session) = self.session.take() {
+                if !session.is_closed() {
+                    return Ok(self.session.get_or_insert(session));
+                }
+            }
+
+            let err = match Connection::connect_with(
+                self.addr.clone(),
+                self.mode,
+                self.password.clone(),
+                self.timeouts,
+            )
+            .await
+            {
+                Ok(session) => return Ok(self.session.get_or_insert(session)),
+                Err(err) => err,
+            };
+
+            // Dialing again won't change the answer.
+            if let Some(HandshakeError::AuthenticationFailed)
+            | Some(HandshakeError::ModeMismatch { .. }) = err.downcast_ref()
+            {
+                return Err(err);
+            }
+
+            self.back_off(attempt, err).await?;
+        }
+    }
+
+    /// Run `command` on the session, started anew and `command` sent again
+    /// if the connection was lost. Dials and commands share the attempts.
+    async fn retrying<R, F>(&mut self, mut command: F) -> Result<R, Error>
+    where
+        F: for<'a> FnMut(
+            &'a mut Session<M>,
+        ) -> Pin<Box<dyn Future<Output = Result<R, Error>> + 'a>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let session = self.open(&mut attempt).await?;
+            let err = match command(session).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+
+            let lost = err.is::<io::Error>() || err.is::<TimedOut>() || session.is_closed();
+            if !lost {
+                return Err(err);
+            }
+
+            self.session = None;
+            self.back_off(&mut attempt, err).await?;
+        }
+    }
+
+    /// Wait before the attempt following `attempt`, or fail with `err`
+    /// once every attempt was made.
+    async fn back_off(&self, attempt: &mut u32, err: Error) -> Result<(), Error> {
+        if *attempt >= self.backoff.attempts {
+            return Err(err);
+        }
+
+        let delay = self.backoff.delay(*attempt);
+        tracing::debug!(attempt = *attempt, ?delay, "connection lost; {}", err);
+
+        tokio::time::delay_for(delay).await;
+        *attempt += 1;
+
+        Ok(())
+    }
+}
+
+impl<A: ToSocketAddrs + Clone> Reconnecting<Search, A> {
+    pub async fn query(&mut self, query: Query) -> Result<Words, Error> {
+        self.retrying(|session| Box::pin(session.query(query.clone())))
+            .await
+    }
+
+    pub async fn suggest(&mut self, suggest: Suggest) -> Result<Words, Error> {
+        self.retrying(|session| Box::pin(session.suggest(suggest.clone())))
+            .await
+    }
+
+    pub async fn list(&mut self, list: List) -> Result<Words, Error> {
+        self.retrying(|session| Box::pin(session.list(list.clone())))
+            .await
+    }
+}
+
+impl<A: ToSocketAddrs + Clone> Reconnecting<Ingest, A> {
+    pub async fn count(&mut self, count: Count) -> Result<u64, Error> {
+        self.retrying(|session| Box::pin(session.count(count.clone())))
+            .await
+    }
+}
+
+impl<A: ToSocketAddrs + Clone> Reconnecting<Control, A> {
+    pub async fn info(&mut self) -> Result<Info, Error> {
+        self.retrying(|session| Box::pin(session.info())).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::fake::{serve, started};
+    use crate::frame::recv::Recv;
+    use crate::frame::send::Send;
+    use crate::frame::Mode;
+    use tokio::net::TcpListener;
+
+    /// Serve each item of `connections` on a connection of its own.
+    /// Returns the frames read.
+    async fn serve_all(listener: &mut TcpListener, connections: Vec<Vec<Vec<Recv>>>) -> Vec<Send> {
+        let mut frames = Vec::new();
+
+        for replies in connections {
+            let (stream, _addr) = listener.accept().await.expect("Failed to accept");
+            frames.extend(serve(stream, replies).await);
+        }
+
+        frames
+    }
+
+    #[test]
+    fn reconnect_backoff() {
+        let backoff = Backoff::default()
+            .initial(Duration::from_millis(100))
+            .max(Duration::from_secs(1));
+
+        for (attempt, max) in &[
+            (0, 100),
+            (1, 200),
+            (2, 400),
+            (3, 800),
+            (4, 1000),
+            (40, 1000),
+        ] {
+            let max = Duration::from_millis(*max);
+            for _ in 0..100 {
+                let delay = backoff.delay(*attempt);
+                assert!(max / 2 <= delay && delay <= max, "{:?}", delay);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnect_retry() {
+        let mut listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind");
+        let addr = listener.local_addr().expect("Failed to get address");
+
+        let server = tokio::spawn(async move {
+            let frames = serve_all(
+                &mut listener,
+                vec![
+                    // Restarting on `QUERY`.
+                    vec![started(Mode::Search), vec![Recv::Pong], vec![]],
+                    vec![
+                        started(Mode::Search),
+                        vec![
+                            Recv::Pending("Bt2m2gYa".into()),
+                            Recv::EventQuery(
+                                "Bt2m2gYa".into(),
+                                vec!["conversation:71f3d63b"].into(),
+                            ),
+                        ],
+                    ],
+                ],
+            )
+            .await;
+
+            // Then rejecting the password.
+            let rejected = serve_all(
+                &mut listener,
+                vec![vec![vec![Recv::Ended("authentication_failed".into())]]],
+            )
+            .await;
+
+            (frames, rejected)
+        });
+
+        let mut search = Reconnecting::new(addr, Search, "SecretPassword".into())
+            .backoff(Backoff::default().initial(Duration::from_millis(1)));
+
+        search.ping().await.expect("Failed to ping");
+
+        let query = Query::new(
+            "messages".into(),
+            "user:0dcde3a6".into(),
+            "valerian saliou".into(),
+        );
+        assert_eq!(
+            Words::from(vec!["conversation:71f3d63b"]),
+            search.query(query.clone()).await.expect("Failed to query")
+        );
+
+        // Not retried, the password won't change.
+        match search.ping().await {
+            Err(err) => assert!(matches!(
+                err.downcast_ref(),
+                Some(HandshakeError::AuthenticationFailed)
+            )),
+            res => panic!("unexpected result; {:?}", res),
+        }
+
+        let start = || Send::Start(Mode::Search, "SecretPassword".into());
+        let (frames, rejected) = server.await.expect("Failed to run server");
+        assert_eq!(
+            vec![
+                start(),
+                Send::Ping,
+                Send::Query(query.clone()),
+                start(),
+                Send::Query(query),
+            ],
+            frames
+        );
+        assert_eq!(vec![start()], rejected);
+    }
+
+    #[tokio::test]
+    async fn reconnect_attempts() {
+        let mut listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind");
+        let addr = listener.local_addr().expect("Failed to get address");
+
+        // Dropping every connection before `CONNECTED`.
+        let server = tokio::spawn(async move {
+            let mut accepted = 0;
+            while let Ok(Ok((_stream, _addr))) =
+                tokio::time::timeout(Duration::from_millis(500), listener.accept()).await
+            {
+                accepted += 1;
+            }
+
+            accepted
+        });
+
+        let mut search = Reconnecting::new(addr, Search, "SecretPassword".into()).backoff(
+            Backoff::default()
+                .initial(Duration::from_millis(1))
+                .attempts(3),
+        );
+
+        assert!(search.ping().await.is_err());
+        assert_eq!(4, server.await.expect("Failed to run server"));
+    }
+}
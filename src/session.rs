@@ -0,0 +1,490 @@
+//! Sessions, as started by `Connection::connect` or `Connection::handshake`.
+//!
+//! The mode is part of the type, so a session only offers the commands
+//! Sonic accepts on it: `SearchSession` queries, `IngestSession` pushes,
+//! `ControlSession` triggers.
+//!
+//! ```no_run
+//! # async fn run() -> my_sonic_client::Result<()> {
+//! use my_sonic_client::connection::Connection;
+//! use my_sonic_client::frame::send::Query;
+//! use my_sonic_client::session::Search;
+//!
+//! let mut session = Connection::connect("[::1]:1491", Search, "SecretPassword".into()).await?;
+//! let keys = session
+//!     .query(Query::new("messages".into(), "user:0dcde3a6".into(), "valerian".into()))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Pushing on a search session doesn't compile:
+//!
+//! ```compile_fail
+//! # async fn run(mut session: my_sonic_client::session::SearchSession) {
+//! use my_sonic_client::frame::send::Push;
+//!
+//! session
+//!     .push(Push::new("messages".into(), "user:0dcde3a6".into(), "conversation:71f3d63b".into(), "Hello".into()))
+//!     .await;
+//! # }
+//! ```
+
+use crate::connection::{Connection, Timeouts};
+use crate::frame::recv::{ErrorKind, Info, Recv, ServerError, ServerInfo, SessionInfo};
+use crate::frame::send::{
+    Count, FlushBucket, FlushCollection, FlushObject, List, Pop, Push, Query, Send, Suggest,
+    Trigger,
+};
+use crate::frame::text::{Text, Words};
+use crate::frame::Mode;
+use crate::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// A mode a session is started on, as a type.
+pub trait SessionMode {
+    const MODE: Mode;
+}
+
+/// `search` mode: `QUERY`, `SUGGEST` and `LIST`.
+#[derive(Clone, Copy, Debug)]
+pub struct Search;
+
+/// `ingest` mode: `PUSH`, `POP`, `COUNT` and `FLUSH*`.
+#[derive(Clone, Copy, Debug)]
+pub struct Ingest;
+
+/// `control` mode: `TRIGGER` and `INFO`.
+#[derive(Clone, Copy, Debug)]
+pub struct Control;
+
+impl SessionMode for Search {
+    const MODE: Mode = Mode::Search;
+}
+
+impl SessionMode for Ingest {
+    const MODE: Mode = Mode::Ingest;
+}
+
+impl SessionMode for Control {
+    const MODE: Mode = Mode::Control;
+}
+
+/// A `Connection` past its handshake: `CONNECTED`, then `START`, answered
+/// with `STARTED` on the mode `M`.
+#[derive(Debug)]
+pub struct Session<M, T = TcpStream> {
+    connection: Connection<T>,
+    server: ServerInfo,
+    info: SessionInfo,
+    mode: PhantomData<M>,
+}
+
+pub type SearchSession<T = TcpStream> = Session<Search, T>;
+pub type IngestSession<T = TcpStream> = Session<Ingest, T>;
+pub type ControlSession<T = TcpStream> = Session<Control, T>;
+
+/// The handshake failed, the connection is dropped.
+#[derive(Debug)]
+pub enum HandshakeError {
+    /// The password was rejected.
+    AuthenticationFailed,
+    /// `STARTED` on another mode than the one asked for.
+    ModeMismatch {
+        expected: Mode,
+        started: Option<Mode>,
+    },
+    /// The server ended the connection, e.g. `ENDED invalid_mode`.
+    Ended(String),
+    /// Another frame than `CONNECTED`, then `STARTED`.
+    Unexpected(Recv),
+}
+
+impl std::error::Error for HandshakeError {}
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandshakeError::AuthenticationFailed => {
+                "handshake failed; authentication failed".fmt(fmt)
+            }
+            HandshakeError::ModeMismatch { expected, started } => match started {
+                Some(started) => write!(
+                    fmt,
+                    "handshake failed; started `{}` instead of `{}`",
+                    started, expected
+                ),
+                None => write!(
+                    fmt,
+                    "handshake failed; started an unknown mode instead of `{}`",
+                    expected
+                ),
+            },
+            HandshakeError::Ended(reason) => write!(fmt, "handshake failed; ended `{}`", reason),
+            HandshakeError::Unexpected(frame) => {
+                write!(fmt, "handshake failed; unexpected frame {:?}", frame)
+            }
+        }
+    }
+}
+
+impl<M: SessionMode, T: AsyncRead + AsyncWrite + Unpin> Session<M, T> {
+    /// Read `CONNECTED`, unless already read, then `START` a session on `M`.
+    pub(crate) async fn start(
+        mut connection: Connection<T>,
+        password: String,
+    ) -> Result<Session<M, T>, Error> {
+        let server = match connection.server_info() {
+            Some(server) => server.clone(),
+            None => match connection.read_frame().await? {
+                Recv::Connected(server) => server,
+                frame => return Err(HandshakeError::Unexpected(frame).into()),
+            },
+        };
+
+        connection
+            .write_frame(Send::Start(M::MODE, password))
+            .await?;
+
+        let info = match connection.read_frame().await {
+            Ok(Recv::Started(info)) => info,
+            Ok(Recv::Ended(reason)) => return Err(ended(reason).into()),
+            Ok(frame) => return Err(HandshakeError::Unexpected(frame).into()),
+            Err(err) => {
+                return match err.downcast_ref::<ServerError>().map(|err| &err.kind) {
+                    Some(ErrorKind::AuthenticationFailed)
+                    | Some(ErrorKind::AuthenticationRequired) => {
+                        Err(HandshakeError::AuthenticationFailed.into())
+                    }
+                    _ => Err(err),
+                };
+            }
+        };
+
+        if info.mode != Some(M::MODE) {
+            return Err(HandshakeError::ModeMismatch {
+                expected: M::MODE,
+                started: info.mode,
+            }
+            .into());
+        }
+
+        Ok(Session {
+            connection,
+            server,
+            info,
+            mode: PhantomData,
+        })
+    }
+
+    /// The mode the session was started on.
+    pub fn mode(&self) -> Mode {
+        M::MODE
+    }
+
+    /// Read from `CONNECTED`.
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server
+    }
+
+    /// Read from `STARTED`.
+    pub fn session_info(&self) -> &SessionInfo {
+        &self.info
+    }
+
+    /// See `Connection::set_timeouts`.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.connection.set_timeouts(timeouts);
+    }
+
+    /// Whether the stream was closed, after a timeout, or by the remote:
+    /// every command fails.
+    pub fn is_closed(&self) -> bool {
+        self.connection.is_closed()
+    }
+
+    /// The underlying connection, to send any frame, valid on `M` or not.
+    pub fn into_connection(self) -> Connection<T> {
+        self.connection
+    }
+
+    pub async fn ping(&mut self) -> Result<(), Error> {
+        match self.call(Send::Ping).await? {
+            Recv::Pong => Ok(()),
+            frame => Err(unexpected(frame)),
+        }
+    }
+
+    /// End the session with `QUIT`, returning the reason of `ENDED`.
+    pub async fn quit(mut self) -> Result<String, Error> {
+        match self.call(Send::Quit).await? {
+            Recv::Ended(reason) => Ok(reason),
+            frame => Err(unexpected(frame)),
+        }
+    }
+
+    /// Write `frame`, then read its reply.
+    async fn call(&mut self, frame: Send) -> Result<Recv, Error> {
+        self.connection.write_frame(frame).await?;
+        self.connection.read_frame().await
+    }
+
+    /// Read `PENDING <id>`, then the `EVENT` of `id`.
+    async fn event(&mut self, frame: Send) -> Result<(Text, Recv), Error> {
+        let id = match self.call(frame).await? {
+            Recv::Pending(id) => id,
+            frame => return Err(unexpected(frame)),
+        };
+
+        let event = self.connection.read_frame().await?;
+        Ok((id, event))
+    }
+
+    /// Read `RESULT <n>`.
+    async fn count_of(&mut self, frame: Send) -> Result<u64, Error> {
+        match self.call(frame).await? {
+            Recv::Result(count) => Ok(count),
+            frame => Err(unexpected(frame)),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Session<Search, T> {
+    /// The objects matching `query`.
+    pub async fn query(&mut self, query: Query) -> Result<Words, Error> {
+        match self.event(Send::Query(query)).await? {
+            (id, Recv::EventQuery(event, keys)) if event == id => Ok(keys),
+            (_id, frame) => Err(unexpected(frame)),
+        }
+    }
+
+    /// The words completing `suggest`.
+    pub async fn suggest(&mut self, suggest: Suggest) -> Result<Words, Error> {
+        match self.event(Send::Suggest(suggest)).await? {
+            (id, Recv::EventSuggest(event, words)) if event == id => Ok(words),
+            (_id, frame) => Err(unexpected(frame)),
+        }
+    }
+
+    /// The words indexed in the bucket, one page of them if `list` has a limit.
+    pub async fn list(&mut self, list: List) -> Result<Words, Error> {
+        match self.event(Send::List(list)).await? {
+            (id, Recv::EventList(event, words)) if event == id => Ok(words),
+            (_id, frame) => Err(unexpected(frame)),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Session<Ingest, T> {
+    /// Index `push`, split to fit the buffer size, as `Connection::push`.
+    pub async fn push(&mut self, push: Push) -> Result<(), Error> {
+        match self.connection.push(push).await? {
+            Recv::Ok => Ok(()),
+            frame => Err(unexpected(frame)),
+        }
+    }
+
+    /// The number of words removed.
+    pub async fn pop(&mut self, pop: Pop) -> Result<u64, Error> {
+        self.count_of(Send::Pop(pop)).await
+    }
+
+    pub async fn count(&mut self, count: Count) -> Result<u64, Error> {
+        self.count_of(Send::Count(count)).await
+    }
+
+    /// The number of items flushed.
+    pub async fn flush_collection(&mut self, flush: FlushCollection) -> Result<u64, Error> {
+        self.count_of(Send::FlushCollection(flush)).await
+    }
+
+    /// The number of items flushed.
+    pub async fn flush_bucket(&mut self, flush: FlushBucket) -> Result<u64, Error> {
+        self.count_of(Send::FlushBucket(flush)).await
+    }
+
+    /// The number of items flushed.
+    pub async fn flush_object(&mut self, flush: FlushObject) -> Result<u64, Error> {
+        self.count_of(Send::FlushObject(flush)).await
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Session<Control, T> {
+    pub async fn trigger(&mut self, trigger: Trigger) -> Result<(), Error> {
+        match self.call(Send::Trigger(trigger)).await? {
+            Recv::Ok => Ok(()),
+            frame => Err(unexpected(frame)),
+        }
+    }
+
+    pub async fn info(&mut self) -> Result<Info, Error> {
+        match self.call(Send::Info).await? {
+            Recv::Info(info) => Ok(info),
+            frame => Err(unexpected(frame)),
+        }
+    }
+}
+
+/// Sonic answers a rejected `START` with `ENDED <reason>`.
+fn ended(reason: String) -> HandshakeError {
+    match reason.as_str() {
+        "authentication_failed" | "authentication_required" => HandshakeError::AuthenticationFailed,
+        _ => HandshakeError::Ended(reason),
+    }
+}
+
+fn unexpected(frame: Recv) -> Error {
+    format!("unexpected frame; {:?}", frame).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::fake::{serve, started};
+    use crate::frame::recv::Version;
+    use tokio::net::UnixStream;
+
+    /// Start a session on a fake server, then `run` it.
+    async fn start<M, F, R>(
+        mode: M,
+        replies: Vec<Vec<Recv>>,
+        run: F,
+    ) -> (Result<R, Error>, Vec<Send>)
+    where
+        M: SessionMode,
+        F: FnOnce(Session<M, UnixStream>) -> R,
+    {
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let server = tokio::spawn(serve(server, replies));
+
+        let session = Connection::new(client)
+            .handshake(mode, "SecretPassword".into())
+            .await
+            .map(run);
+
+        (session, server.await.expect("Failed to run server"))
+    }
+
+    fn handshake_error<R>(res: Result<R, Error>) -> HandshakeError {
+        match res.map_err(|err| err.downcast::<HandshakeError>()) {
+            Err(Ok(err)) => *err,
+            _ => panic!("Failed to fail handshake"),
+        }
+    }
+
+    #[tokio::test]
+    async fn session_handshake() {
+        let (session, frames) = start(Search, vec![started(Mode::Search)], |session| {
+            assert_eq!(Mode::Search, session.mode());
+            assert_eq!(Version::new(1, 2, 3), session.server_info().version);
+            assert_eq!(20000, session.session_info().buffer_size);
+        })
+        .await;
+
+        session.expect("Failed to start session");
+        assert_eq!(
+            vec![Send::Start(Mode::Search, "SecretPassword".into())],
+            frames
+        );
+
+        let (session, _frames) = start(
+            Ingest,
+            vec![vec![Recv::Ended("authentication_failed".into())]],
+            drop,
+        )
+        .await;
+        assert!(matches!(
+            handshake_error(session),
+            HandshakeError::AuthenticationFailed
+        ));
+
+        let (session, _frames) = start(Ingest, vec![started(Mode::Search)], drop).await;
+        assert!(matches!(
+            handshake_error(session),
+            HandshakeError::ModeMismatch {
+                expected: Mode::Ingest,
+                started: Some(Mode::Search)
+            }
+        ));
+
+        let (session, _frames) = start(Control, vec![vec![Recv::Pong]], drop).await;
+        assert!(matches!(
+            handshake_error(session),
+            HandshakeError::Unexpected(Recv::Pong)
+        ));
+    }
+
+    #[tokio::test]
+    async fn session_commands() {
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let server = tokio::spawn(serve(
+            server,
+            vec![
+                started(Mode::Search),
+                vec![
+                    Recv::Pending("Bt2m2gYa".into()),
+                    Recv::EventQuery(
+                        "Bt2m2gYa".into(),
+                        Words::from(vec!["conversation:71f3d63b"]),
+                    ),
+                ],
+                vec![Recv::Pong],
+                vec![Recv::Ended("quit".into())],
+            ],
+        ));
+
+        let mut session = Connection::new(client)
+            .handshake(Search, "SecretPassword".into())
+            .await
+            .expect("Failed to start session");
+
+        assert_eq!(
+            Words::from(vec!["conversation:71f3d63b"]),
+            session
+                .query(Query::new(
+                    "messages".into(),
+                    "user:0dcde3a6".into(),
+                    "valerian".into()
+                ))
+                .await
+                .expect("Failed to query")
+        );
+        session.ping().await.expect("Failed to ping");
+        assert_eq!("quit", session.quit().await.expect("Failed to quit"));
+
+        assert_eq!(4, server.await.expect("Failed to run server").len());
+
+        let (client, server) = UnixStream::pair().expect("Failed to create UnixStream pair.");
+        let server = tokio::spawn(serve(
+            server,
+            vec![started(Mode::Ingest), vec![Recv::Ok], vec![Recv::Result(3)]],
+        ));
+
+        let mut session = Connection::new(client)
+            .handshake(Ingest, "SecretPassword".into())
+            .await
+            .expect("Failed to start session");
+
+        session
+            .push(Push::new(
+                "messages".into(),
+                "user:0dcde3a6".into(),
+                "conversation:71f3d63b".into(),
+                "Hello".into(),
+            ))
+            .await
+            .expect("Failed to push");
+        assert_eq!(
+            3,
+            session
+                .count(Count::new("messages".into()))
+                .await
+                .expect("Failed to count")
+        );
+
+        server.await.expect("Failed to run server");
+    }
+}